@@ -0,0 +1,913 @@
+// Answer checking shared by every mode.
+//
+// Both sides of the comparison are first run through the normalisation pipeline,
+// which collections can override (default: nfc, trim, whitespace, case).
+// `case` only lowercases, `punctuation` strips the Unicode punctuation categories
+// (dashes become spaces) and collapses the whitespace left behind:
+// $ normalise = nfc, trim, whitespace, case, punctuation
+//
+// Collections can declare which script differences should not count as mistakes:
+// $ equivalence = kana, width, han, ß=ss
+// kana  - hiragana and katakana are the same
+// width - full-width latin/digits are the same as half-width
+// han   - traditional and simplified chinese characters are the same, only for the
+//         ~80 common characters listed in HAN_VARIANTS, not the full set
+// a=b=c - custom class, any of the listed strings are the same, compared after normalisation
+//
+// Missing diacritics (zolw for żółw) are wrong by default, collections can relax it:
+// $ accents = strict | accept | almost
+// accept - answer is accepted as a variant
+// almost - answer is graded as almost correct, listing the characters that were missed
+//
+// Small typos can be graded separately from wrong answers (off by default):
+// $ typos = 2, 5
+// at most 2 edits (insert, delete, substitute, swap neighbours), one per every full 5 letters,
+// so answers shorter than 5 letters have to be exact
+//
+// Entries with several meanings can require all of them, typed in any order (also a session option):
+// $ require_all = true
+// $ separator = ,
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_properties::{GeneralCategory, GeneralCategoryGroup, UnicodeGeneralCategory};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::collection::Collection;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalisation {
+    Nfc,
+    Trim,
+    CollapseWhitespace,
+    // `to_lowercase`, not full case folding: ß stays ß and ς stays ς,
+    // collections that need it can add an equivalence like ß=ss
+    Lowercase,
+    // anything in the Unicode punctuation categories (P*), symbols like $ or + are kept.
+    // Dashes and connectors split words ("well-known" is "well known"),
+    // and the whitespace left behind is collapsed
+    StripPunctuation,
+}
+
+impl Normalisation {
+    pub const DEFAULT: [Normalisation; 4] = [
+        Normalisation::Nfc,
+        Normalisation::Trim,
+        Normalisation::CollapseWhitespace,
+        Normalisation::Lowercase,
+    ];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "nfc" => Some(Normalisation::Nfc),
+            "trim" => Some(Normalisation::Trim),
+            "whitespace" => Some(Normalisation::CollapseWhitespace),
+            "case" => Some(Normalisation::Lowercase),
+            "punctuation" => Some(Normalisation::StripPunctuation),
+            _ => None,
+        }
+    }
+
+    pub fn parse_list(s: &str) -> Vec<Self> {
+        s.split(',')
+            .filter(|v| !v.trim().is_empty())
+            .filter_map(|v| {
+                let n = Self::parse(v);
+                if n.is_none() {
+                    println!("unknown normalisation [ {} ]", v.trim());
+                }
+                n
+            })
+            .collect()
+    }
+
+    fn apply(&self, input: &str) -> String {
+        match self {
+            Normalisation::Nfc => input.nfc().collect(),
+            Normalisation::Trim => input.trim().to_owned(),
+            Normalisation::CollapseWhitespace => {
+                input.split_whitespace().collect::<Vec<_>>().join(" ")
+            }
+            Normalisation::Lowercase => input.to_lowercase(),
+            Normalisation::StripPunctuation => input
+                .chars()
+                .filter_map(|c| match c.general_category() {
+                    GeneralCategory::DashPunctuation | GeneralCategory::ConnectorPunctuation => {
+                        Some(' ')
+                    }
+                    _ if c.general_category_group() == GeneralCategoryGroup::Punctuation => None,
+                    _ => Some(c),
+                })
+                .collect::<String>()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccentMode {
+    Strict,
+    Accept,
+    Almost,
+}
+
+impl AccentMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "strict" => Some(AccentMode::Strict),
+            "accept" => Some(AccentMode::Accept),
+            "almost" => Some(AccentMode::Almost),
+            _ => None,
+        }
+    }
+}
+
+fn is_combining_accent(c: char) -> bool {
+    ('\u{0300}'..='\u{036F}').contains(&c)
+}
+
+// letters with a stroke have no decomposition
+fn strip_stroke(c: char) -> char {
+    match c {
+        'ł' => 'l',
+        'Ł' => 'L',
+        'ø' => 'o',
+        'Ø' => 'O',
+        'đ' => 'd',
+        'Đ' => 'D',
+        'ħ' => 'h',
+        'Ħ' => 'H',
+        _ => c,
+    }
+}
+
+// only the combining diacritics go, so Hangul syllables and kana voicing marks,
+// which decompose as well, come back whole
+fn strip_accents(input: &str) -> String {
+    input
+        .nfd()
+        .filter(|c| !is_combining_accent(*c))
+        .map(strip_stroke)
+        .nfc()
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypoTolerance {
+    pub max_edits: usize,
+    pub graphemes_per_edit: usize,
+}
+
+impl TypoTolerance {
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.split(',').map(|v| v.trim().parse::<usize>());
+        let max_edits = parts.next()?.ok()?;
+        let graphemes_per_edit = match parts.next() {
+            Some(v) => v.ok()?.max(1),
+            None => Self::default().graphemes_per_edit,
+        };
+        Some(Self {
+            max_edits,
+            graphemes_per_edit,
+        })
+    }
+
+    // answers shorter than `graphemes_per_edit` have to be exact
+    pub fn allowed_edits(&self, len: usize) -> usize {
+        (len / self.graphemes_per_edit).min(self.max_edits)
+    }
+}
+
+impl Default for TypoTolerance {
+    fn default() -> Self {
+        Self {
+            max_edits: 2,
+            graphemes_per_edit: 5,
+        }
+    }
+}
+
+// optimal string alignment distance over grapheme clusters
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.graphemes(true).collect::<Vec<_>>();
+    let b = b.graphemes(true).collect::<Vec<_>>();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffPart {
+    Same(String),
+    // in the expected answer but not typed
+    Missing(String),
+    // typed but not in the expected answer
+    Extra(String),
+}
+
+// grapheme level diff of the input against the expected answer (longest common subsequence)
+pub fn diff(expected: &str, input: &str) -> Vec<DiffPart> {
+    let e = expected.graphemes(true).collect::<Vec<_>>();
+    let i = input.graphemes(true).collect::<Vec<_>>();
+    let mut lcs = vec![vec![0; i.len() + 1]; e.len() + 1];
+    for x in (0..e.len()).rev() {
+        for y in (0..i.len()).rev() {
+            lcs[x][y] = if e[x] == i[y] {
+                lcs[x + 1][y + 1] + 1
+            } else {
+                lcs[x + 1][y].max(lcs[x][y + 1])
+            };
+        }
+    }
+
+    let mut parts: Vec<DiffPart> = Vec::new();
+    let mut push = |part: DiffPart| match (parts.last_mut(), part) {
+        (Some(DiffPart::Same(s)), DiffPart::Same(g))
+        | (Some(DiffPart::Missing(s)), DiffPart::Missing(g))
+        | (Some(DiffPart::Extra(s)), DiffPart::Extra(g)) => s.push_str(&g),
+        (_, part) => parts.push(part),
+    };
+    let (mut x, mut y) = (0, 0);
+    while x < e.len() || y < i.len() {
+        if x < e.len() && y < i.len() && e[x] == i[y] {
+            push(DiffPart::Same(e[x].to_owned()));
+            x += 1;
+            y += 1;
+        } else if x < e.len() && (y == i.len() || lcs[x + 1][y] >= lcs[x][y + 1]) {
+            push(DiffPart::Missing(e[x].to_owned()));
+            x += 1;
+        } else {
+            push(DiffPart::Extra(i[y].to_owned()));
+            y += 1;
+        }
+    }
+    parts
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence {
+    Kana,
+    Width,
+    Han,
+    Custom(Vec<String>),
+}
+
+impl Equivalence {
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s.contains('=') {
+            let class = s
+                .split('=')
+                .map(|v| v.trim().to_owned())
+                .filter(|v| !v.is_empty())
+                .collect::<Vec<_>>();
+            return if class.len() > 1 {
+                Some(Equivalence::Custom(class))
+            } else {
+                None
+            };
+        }
+        match s.to_lowercase().as_str() {
+            "kana" => Some(Equivalence::Kana),
+            "width" => Some(Equivalence::Width),
+            "han" => Some(Equivalence::Han),
+            _ => None,
+        }
+    }
+
+    pub fn parse_list(s: &str) -> Vec<Self> {
+        s.split(',')
+            .filter(|v| !v.trim().is_empty())
+            .filter_map(|v| {
+                let eq = Self::parse(v);
+                if eq.is_none() {
+                    println!("unknown equivalence [ {} ]", v.trim());
+                }
+                eq
+            })
+            .collect()
+    }
+
+    fn fold(&self, input: &str) -> String {
+        match self {
+            Equivalence::Kana => input.chars().map(fold_kana).collect(),
+            Equivalence::Width => input.chars().map(fold_width).collect(),
+            Equivalence::Han => input.chars().map(fold_han).collect(),
+            Equivalence::Custom(class) => {
+                let mut out = input.to_owned();
+                if let Some((first, alts)) = class.split_first() {
+                    for alt in alts {
+                        out = out.replace(alt.as_str(), first);
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Correct,
+    // correct only after applying equivalences
+    Variant,
+    // correct except for diacritics, `missing` are the expected characters that were not typed
+    Almost {
+        expected: String,
+        missing: Vec<char>,
+    },
+    // within the typo tolerance of `expected`
+    Typo {
+        expected: String,
+        distance: usize,
+    },
+    // only some of the required meanings were given, or some only with a typo
+    Partial {
+        given: Vec<String>,
+        // given within the typo or accent tolerance, worth half
+        typos: Vec<String>,
+        missed: Vec<String>,
+        wrong: Vec<String>,
+    },
+    // input is a meaning of another entry of the same collection
+    Confused {
+        other: String,
+    },
+    Incorrect,
+}
+
+impl Verdict {
+    pub fn is_correct(&self) -> bool {
+        matches!(self, Verdict::Correct | Verdict::Variant)
+    }
+
+    // partial credit used for test scores
+    pub fn score(&self) -> f32 {
+        match self {
+            Verdict::Correct | Verdict::Variant => 1.,
+            Verdict::Almost { .. } | Verdict::Typo { .. } => 0.5,
+            Verdict::Partial { given, typos, .. } => {
+                (given.len() as f32 + typos.len() as f32 * 0.5) / self.parts() as f32
+            }
+            Verdict::Confused { .. } | Verdict::Incorrect => 0.,
+        }
+    }
+
+    // required meanings plus wrong extra parts, so guessing more doesn't pay off
+    fn parts(&self) -> usize {
+        match self {
+            Verdict::Partial {
+                given,
+                typos,
+                missed,
+                wrong,
+            } => given.len() + typos.len() + missed.len() + wrong.len(),
+            _ => 1,
+        }
+    }
+
+    // short name saved in the review log
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Verdict::Correct => "correct",
+            Verdict::Variant => "variant",
+            Verdict::Almost { .. } => "almost",
+            Verdict::Typo { .. } => "typo",
+            Verdict::Partial { .. } => "partial",
+            Verdict::Confused { .. } => "confused",
+            Verdict::Incorrect => "incorrect",
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Verdict::Correct => "Correct".into(),
+            Verdict::Variant => "Correct (variant)".into(),
+            Verdict::Almost { missing, .. } => format!("Almost ({})", join_chars(missing)),
+            Verdict::Typo { expected, .. } => format!("Typo ({expected})"),
+            Verdict::Partial { given, .. } => format!("Partial ({}/{})", given.len(), self.parts()),
+            Verdict::Confused { other } => format!("Confused with {other}"),
+            Verdict::Incorrect => "Incorrect".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnswerChecker {
+    normalisation: Vec<Normalisation>,
+    // as given by the collection
+    equivalences: Vec<Equivalence>,
+    // the same with custom classes normalised, so they match normalised input
+    folds: Vec<Equivalence>,
+    accents: AccentMode,
+    typos: Option<TypoTolerance>,
+    // separator between meanings when all of them are required
+    require_all: Option<String>,
+}
+
+impl AnswerChecker {
+    pub fn new() -> Self {
+        Self {
+            normalisation: Normalisation::DEFAULT.to_vec(),
+            equivalences: Vec::new(),
+            folds: Vec::new(),
+            accents: AccentMode::Strict,
+            typos: None,
+            require_all: None,
+        }
+    }
+
+    pub fn for_collection(coll: &Collection) -> Self {
+        let mut checker = Self::new().with_equivalences(coll.equivalences());
+        if let Some(n) = coll.variable("normalise") {
+            checker = checker.with_normalisation(Normalisation::parse_list(n));
+        }
+        if let Some(a) = coll.variable("accents") {
+            match AccentMode::parse(a) {
+                Some(a) => checker = checker.with_accents(a),
+                None => println!("unknown accent mode [ {a} ]"),
+            }
+        }
+        if let Some(t) = coll.variable("typos") {
+            match TypoTolerance::parse(t) {
+                Some(t) => checker = checker.with_typos(Some(t)),
+                None => println!("invalid typo tolerance [ {t} ]"),
+            }
+        }
+        if coll.variable("require_all") == Some("true") {
+            checker = checker.with_require_all(true, coll.variable("separator"));
+        }
+        checker
+    }
+
+    // the collection's checker, with every meaning required when the session asks for it
+    pub fn for_session(coll: &Collection, require_all: bool) -> Self {
+        let checker = Self::for_collection(coll);
+        if require_all {
+            checker.with_require_all(true, coll.variable("separator"))
+        } else {
+            checker
+        }
+    }
+
+    pub fn with_normalisation(mut self, normalisation: Vec<Normalisation>) -> Self {
+        self.normalisation = normalisation;
+        self.update_folds();
+        self
+    }
+
+    pub fn with_equivalences(mut self, equivalences: Vec<Equivalence>) -> Self {
+        self.equivalences = equivalences;
+        self.update_folds();
+        self
+    }
+
+    fn update_folds(&mut self) {
+        self.folds = self
+            .equivalences
+            .iter()
+            .map(|eq| match eq {
+                Equivalence::Custom(class) => {
+                    // a string normalised away would match everywhere
+                    let class = class
+                        .iter()
+                        .map(|v| self.normalise(v))
+                        .filter(|v| !v.is_empty())
+                        .collect::<Vec<_>>();
+                    Equivalence::Custom(class)
+                }
+                eq => eq.clone(),
+            })
+            .collect();
+    }
+
+    pub fn with_accents(mut self, accents: AccentMode) -> Self {
+        self.accents = accents;
+        self
+    }
+
+    pub fn with_typos(mut self, typos: Option<TypoTolerance>) -> Self {
+        self.typos = typos;
+        self
+    }
+
+    pub fn with_require_all(mut self, require_all: bool, separator: Option<&str>) -> Self {
+        self.require_all = require_all.then(|| separator.unwrap_or(",").to_owned());
+        self
+    }
+
+    pub fn requires_all(&self) -> bool {
+        self.require_all.is_some()
+    }
+
+    pub fn normalise(&self, input: &str) -> String {
+        self.normalisation
+            .iter()
+            .fold(input.to_owned(), |acc, n| n.apply(&acc))
+    }
+
+    pub fn fold(&self, input: &str) -> String {
+        self.folds
+            .iter()
+            .fold(self.normalise(input), |acc, eq| eq.fold(&acc))
+    }
+
+    // accepted answer closest to the input
+    pub fn closest<'a>(&self, meanings: &'a [String], input: &str) -> Option<&'a String> {
+        let folded = self.fold(input);
+        meanings
+            .iter()
+            .min_by_key(|m| edit_distance(&self.fold(m), &folded))
+    }
+
+    pub fn check(&self, meanings: &[String], input: &str) -> Verdict {
+        match &self.require_all {
+            Some(separator) if meanings.len() > 1 => self.check_all(meanings, input, separator),
+            _ => self.check_any(meanings, input),
+        }
+    }
+
+    // like `check`, but wrong answers that belong to another entry are reported as confusions
+    pub fn check_entry(&self, coll: &Collection, word: &str, input: &str) -> Verdict {
+        let Some(meanings) = coll.words().get(word) else {
+            return Verdict::Incorrect;
+        };
+        let verdict = self.check(meanings, input);
+        if verdict != Verdict::Incorrect {
+            return verdict;
+        }
+        coll.words()
+            .iter()
+            .filter(|(other, _)| other.as_str() != word)
+            .find(|(_, meanings)| self.check_any(meanings, input).is_correct())
+            .map_or(verdict, |(other, _)| Verdict::Confused {
+                other: other.clone(),
+            })
+    }
+
+    // answer to a meaning shown as the prompt, any word with that meaning is accepted
+    pub fn check_meaning(&self, coll: &Collection, meaning: &str, input: &str) -> Verdict {
+        self.check_any(coll.words_for(meaning), input)
+    }
+
+    // every meaning has to be given, in any order
+    fn check_all(&self, meanings: &[String], input: &str, separator: &str) -> Verdict {
+        let mut missed = meanings.to_vec();
+        let mut given = Vec::new();
+        let mut typos = Vec::new();
+        let mut wrong = Vec::new();
+        for part in input
+            .split(separator)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+        {
+            let verdicts = missed
+                .iter()
+                .map(|m| self.check_any(std::slice::from_ref(m), part))
+                .collect::<Vec<_>>();
+            // an exact match wins over a typo of another meaning
+            let exact = verdicts.iter().position(Verdict::is_correct);
+            let close = verdicts.iter().position(|v| v.score() > 0.);
+            match (exact, close) {
+                (Some(i), _) => given.push(missed.remove(i)),
+                (None, Some(i)) => typos.push(missed.remove(i)),
+                (None, None) => wrong.push(part.to_owned()),
+            }
+        }
+        if given.is_empty() && typos.is_empty() {
+            Verdict::Incorrect
+        } else if typos.is_empty() && missed.is_empty() && wrong.is_empty() {
+            Verdict::Correct
+        } else {
+            Verdict::Partial {
+                given,
+                typos,
+                missed,
+                wrong,
+            }
+        }
+    }
+
+    fn check_any(&self, meanings: &[String], input: &str) -> Verdict {
+        let normalised = self.normalise(input);
+        if meanings.iter().any(|m| self.normalise(m) == normalised) {
+            return Verdict::Correct;
+        }
+        let folded = self.fold(input);
+        if meanings.iter().any(|m| self.fold(m) == folded) {
+            return Verdict::Variant;
+        }
+        if self.accents != AccentMode::Strict {
+            let stripped = strip_accents(&folded);
+            if let Some(m) = meanings
+                .iter()
+                .find(|m| strip_accents(&self.fold(m)) == stripped)
+            {
+                return match self.accents {
+                    AccentMode::Almost => Verdict::Almost {
+                        expected: m.clone(),
+                        missing: missing_accents(&self.fold(m), &folded),
+                    },
+                    _ => Verdict::Variant,
+                };
+            }
+        }
+        if let Some(typos) = self.typos {
+            let closest = meanings
+                .iter()
+                .map(|m| (m, edit_distance(&self.fold(m), &folded)))
+                .min_by_key(|(_, d)| *d);
+            if let Some((m, distance)) = closest {
+                let len = self.fold(m).graphemes(true).count();
+                if distance <= typos.allowed_edits(len) {
+                    return Verdict::Typo {
+                        expected: m.clone(),
+                        distance,
+                    };
+                }
+            }
+        }
+        Verdict::Incorrect
+    }
+}
+
+pub const MAX_HINTS: usize = 3;
+
+// first letter, then half of the letters, then also the number of words
+pub fn hint(answer: &str, level: usize) -> String {
+    let graphemes = answer.graphemes(true).collect::<Vec<_>>();
+    let shown = match level {
+        0 => return String::new(),
+        1 => 1,
+        _ => graphemes.len().div_ceil(2),
+    };
+    let mut hint = graphemes[..shown.min(graphemes.len())].concat() + "…";
+    if level >= MAX_HINTS {
+        let words = answer.split_whitespace().count();
+        hint += &format!(" ({words} word{})", if words == 1 { "" } else { "s" });
+    }
+    hint
+}
+
+// share of the score kept after using `hints` hints
+pub fn hint_penalty(hints: usize) -> f32 {
+    1. - 0.25 * hints.min(MAX_HINTS) as f32
+}
+
+pub fn join_chars(chars: &[char]) -> String {
+    chars
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// expected characters whose accents differ from what was typed,
+// both strings must be equal once accents are stripped
+fn missing_accents(expected: &str, input: &str) -> Vec<char> {
+    let expected = expected.nfc().filter(|c| !is_combining_accent(*c));
+    let input = input.nfc().filter(|c| !is_combining_accent(*c));
+    let mut missing = Vec::new();
+    for (e, i) in expected.zip(input) {
+        if e != i && !missing.contains(&e) {
+            missing.push(e);
+        }
+    }
+    missing
+}
+
+impl Default for AnswerChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// katakana is laid out exactly 0x60 above hiragana
+fn fold_kana(c: char) -> char {
+    match c {
+        '\u{30A1}'..='\u{30F6}' | '\u{30FD}'..='\u{30FE}' => {
+            char::from_u32(c as u32 - 0x60).unwrap_or(c)
+        }
+        _ => c,
+    }
+}
+
+fn fold_width(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+fn fold_han(c: char) -> char {
+    HAN_VARIANTS
+        .iter()
+        .find(|(trad, _)| *trad == c)
+        .map_or(c, |(_, simp)| *simp)
+}
+
+// (traditional, simplified) for the most common characters only,
+// any other character is compared as it is
+const HAN_VARIANTS: &[(char, char)] = &[
+    ('國', '国'),
+    ('學', '学'),
+    ('語', '语'),
+    ('說', '说'),
+    ('話', '话'),
+    ('讀', '读'),
+    ('書', '书'),
+    ('寫', '写'),
+    ('聽', '听'),
+    ('見', '见'),
+    ('來', '来'),
+    ('時', '时'),
+    ('們', '们'),
+    ('個', '个'),
+    ('這', '这'),
+    ('還', '还'),
+    ('進', '进'),
+    ('過', '过'),
+    ('對', '对'),
+    ('開', '开'),
+    ('關', '关'),
+    ('門', '门'),
+    ('問', '问'),
+    ('間', '间'),
+    ('車', '车'),
+    ('東', '东'),
+    ('長', '长'),
+    ('馬', '马'),
+    ('魚', '鱼'),
+    ('鳥', '鸟'),
+    ('飛', '飞'),
+    ('電', '电'),
+    ('氣', '气'),
+    ('愛', '爱'),
+    ('體', '体'),
+    ('頭', '头'),
+    ('買', '买'),
+    ('賣', '卖'),
+    ('錢', '钱'),
+    ('飯', '饭'),
+    ('飲', '饮'),
+    ('喫', '吃'),
+    ('樂', '乐'),
+    ('歲', '岁'),
+    ('號', '号'),
+    ('後', '后'),
+    ('裡', '里'),
+    ('裏', '里'),
+    ('會', '会'),
+    ('點', '点'),
+    ('幾', '几'),
+    ('兩', '两'),
+    ('萬', '万'),
+    ('興', '兴'),
+    ('漢', '汉'),
+    ('詞', '词'),
+    ('請', '请'),
+    ('謝', '谢'),
+    ('認', '认'),
+    ('識', '识'),
+    ('親', '亲'),
+    ('視', '视'),
+    ('覺', '觉'),
+    ('員', '员'),
+    ('師', '师'),
+    ('場', '场'),
+    ('發', '发'),
+    ('現', '现'),
+    ('實', '实'),
+    ('應', '应'),
+    ('歡', '欢'),
+    ('業', '业'),
+    ('農', '农'),
+    ('藝', '艺'),
+    ('醫', '医'),
+    ('藥', '药'),
+    ('風', '风'),
+    ('雲', '云'),
+    ('紅', '红'),
+    ('綠', '绿'),
+    ('藍', '蓝'),
+    ('黃', '黄'),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meanings(list: &[&str]) -> Vec<String> {
+        list.iter().map(|m| m.to_string()).collect()
+    }
+
+    #[test]
+    fn short_answers_have_no_typos() {
+        let checker = AnswerChecker::new().with_typos(Some(TypoTolerance::default()));
+        assert_eq!(checker.check(&meanings(&["I"]), "a"), Verdict::Incorrect);
+        assert_eq!(checker.check(&meanings(&["木"]), "本"), Verdict::Incorrect);
+        assert_eq!(checker.check(&meanings(&["I"]), ""), Verdict::Incorrect);
+        assert!(matches!(
+            checker.check(&meanings(&["house"]), "hause"),
+            Verdict::Typo { distance: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn require_all_gives_half_credit_for_typos() {
+        let checker = AnswerChecker::new()
+            .with_typos(Some(TypoTolerance::default()))
+            .with_require_all(true, None);
+        let house = meanings(&["house", "building"]);
+        assert_eq!(checker.check(&house, "building, house"), Verdict::Correct);
+        let verdict = checker.check(&house, "buildinq, hause");
+        assert!(matches!(&verdict, Verdict::Partial { typos, .. } if typos.len() == 2));
+        assert_eq!(verdict.score(), 0.5);
+        let verdict = checker.check(&house, "house, buildinq");
+        assert_eq!(verdict.score(), 0.75);
+        let verdict = checker.check(&house, "house, building, cat");
+        assert!(!verdict.is_correct());
+        assert_eq!(verdict.label(), "Partial (2/3)");
+        assert!((verdict.score() - 2. / 3.).abs() < 1e-6);
+        assert_eq!(checker.check(&house, "cat, dog"), Verdict::Incorrect);
+    }
+
+    #[test]
+    fn strip_unicode_punctuation() {
+        let strip = Normalisation::StripPunctuation;
+        assert_eq!(
+            strip.apply("«Bonjour!» ¿Qué? „tak“ – l’eau"),
+            "Bonjour Qué tak leau"
+        );
+        assert_eq!(strip.apply("well-known"), "well known");
+        let checker = AnswerChecker::new().with_normalisation(Normalisation::parse_list(
+            "nfc, trim, whitespace, case, punctuation",
+        ));
+        assert!(checker.check(&meanings(&["a – b"]), "a b").is_correct());
+        assert!(checker
+            .check(&meanings(&["well-known"]), "well known")
+            .is_correct());
+        assert_eq!(strip.apply("「猫」です。"), "猫です");
+        assert_eq!(strip.apply("々 $5"), "々 $5");
+    }
+
+    #[test]
+    fn accents_keep_hangul_and_kana_whole() {
+        let checker = AnswerChecker::new().with_accents(AccentMode::Accept);
+        assert_eq!(
+            checker.check(&meanings(&["한국"]), "하고"),
+            Verdict::Incorrect
+        );
+        assert_eq!(
+            checker.check(&meanings(&["がっこう"]), "かっこう"),
+            Verdict::Incorrect
+        );
+        assert_eq!(
+            checker.check(&meanings(&["café"]), "cafe"),
+            Verdict::Variant
+        );
+    }
+
+    #[test]
+    fn missing_accents_are_listed() {
+        let checker = AnswerChecker::new().with_accents(AccentMode::Almost);
+        assert_eq!(
+            checker.check(&meanings(&["żółw"]), "zolw"),
+            Verdict::Almost {
+                expected: "żółw".into(),
+                missing: vec!['ż', 'ó', 'ł'],
+            }
+        );
+    }
+
+    #[test]
+    fn custom_classes_are_normalised() {
+        let checker = AnswerChecker::new()
+            .with_equivalences(Equivalence::parse_list("ß=SS"))
+            .with_normalisation(Normalisation::DEFAULT.to_vec());
+        assert_eq!(
+            checker.check(&meanings(&["Straße"]), "STRASSE"),
+            Verdict::Variant
+        );
+        assert_eq!(
+            checker.check(&meanings(&["strasse"]), "straße"),
+            Verdict::Variant
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use either::Either::{self, Left, Right};
+use iced::{
+    keyboard::{key::Named, Key},
+    widget::{button, column, container, text},
+    Alignment::Center,
+    Color, Element,
+    Length::Fill,
+    Task,
+};
+use rand::seq::SliceRandom;
+
+use crate::{
+    checker::{edit_distance, Verdict},
+    collection::Collection,
+    store::{self, Review},
+    Action,
+};
+
+const OPTIONS: usize = 4;
+// distractor difficulty, 0 picks them at random
+pub const MAX_DIFFICULTY: u8 = 10;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Start(Arc<Collection>, usize, u8),
+    Picked(usize),
+    KeyPressed(Key),
+}
+
+#[derive(Debug, Clone)]
+pub struct ChoiceWidget {
+    selected_collection: Option<Arc<Collection>>,
+    words: Vec<String>,
+    word_index: usize,
+    // entries whose meanings are offered, one of them is the current word
+    options: Vec<String>,
+    picked: Option<usize>,
+    difficulty: u8,
+    // (entry, confused with, count) from the history of the collection
+    confusions: Vec<(String, String, usize)>,
+    correct: usize,
+    end_message: bool,
+    // when the current word was shown, in ms
+    shown_at: i64,
+    // when the session started, in ms
+    session: i64,
+    // last error saving to the store, shown on the screen
+    save_status: Option<String>,
+}
+
+// 0 for unrelated entries up to 1 for ones easily mistaken for `word`
+fn similarity(coll: &Collection, word: &str, other: &str, confused: bool) -> f64 {
+    let closeness = |a: &str, b: &str| {
+        let len = a.chars().count().max(b.chars().count()).max(1) as f64;
+        1. - edit_distance(a, b) as f64 / len
+    };
+    let meaning = coll.words()[word].join(" / ");
+    let other_meaning = coll.words()[other].join(" / ");
+    let spelling = closeness(word, other).max(closeness(&meaning, &other_meaning));
+    let (len, other_len) = (meaning.chars().count(), other_meaning.chars().count());
+    let length = 1. - len.abs_diff(other_len) as f64 / len.max(other_len).max(1) as f64;
+    let tag = coll.tags(word).iter().any(|t| coll.tags(other).contains(t));
+    (3. * confused as u8 as f64 + 2. * tag as u8 as f64 + 2. * spelling + length) / 8.
+}
+
+// other entries to offer next to `word`, none of them shares a meaning with it.
+// the higher the `difficulty` the more likely similar entries are picked
+pub fn distractors(
+    coll: &Collection,
+    word: &str,
+    count: usize,
+    difficulty: u8,
+    confusions: &[(String, String, usize)],
+) -> Vec<String> {
+    let meanings = &coll.words()[word];
+    let candidates = coll
+        .words()
+        .iter()
+        .filter(|(w, m)| *w != word && !m.iter().any(|m| meanings.contains(m)))
+        .map(|(w, _)| {
+            let confused = confusions
+                .iter()
+                .any(|(a, b, _)| (a == word && b == w) || (a == w && b == word));
+            let weight = (difficulty as f64 * similarity(coll, word, w, confused)).exp();
+            (w.clone(), weight)
+        })
+        .collect::<Vec<_>>();
+    candidates
+        .choose_multiple_weighted(&mut rand::thread_rng(), count, |(_, weight)| *weight)
+        .map(|chosen| chosen.map(|(w, _)| w.clone()).collect())
+        .unwrap_or_default()
+}
+
+impl ChoiceWidget {
+    pub fn new() -> Self {
+        Self {
+            selected_collection: None,
+            words: Vec::new(),
+            word_index: 0,
+            options: Vec::new(),
+            picked: None,
+            difficulty: 0,
+            confusions: Vec::new(),
+            correct: 0,
+            end_message: false,
+            shown_at: 0,
+            session: 0,
+            save_status: None,
+        }
+    }
+
+    fn show_word(&mut self) {
+        let coll = self.selected_collection.as_ref().unwrap();
+        let word = &self.words[self.word_index];
+        self.options = distractors(coll, word, OPTIONS - 1, self.difficulty, &self.confusions);
+        self.options.push(word.clone());
+        self.options.shuffle(&mut rand::thread_rng());
+        self.picked = None;
+        self.shown_at = Utc::now().timestamp_millis();
+    }
+
+    fn pick(&mut self, i: usize) {
+        if self.picked.is_some() || i >= self.options.len() {
+            return;
+        }
+        let coll = self.selected_collection.clone().unwrap();
+        let word = &self.words[self.word_index];
+        let option = &self.options[i];
+        // picking another entry's meaning is remembered as a confusion
+        let verdict = if option == word {
+            self.correct += 1;
+            Verdict::Correct
+        } else {
+            Verdict::Confused {
+                other: option.clone(),
+            }
+        };
+        let review = Review::new(
+            coll.key(),
+            word,
+            "choice",
+            &coll.words()[option].join(" / "),
+            &verdict,
+            self.shown_at,
+            self.session,
+        );
+        store::or_report(&mut self.save_status, "save review", |s| {
+            s.record_answer(&review, &verdict)
+        });
+        self.picked = Some(i);
+    }
+
+    fn next(&mut self) {
+        if self.word_index + 1 < self.words.len() {
+            self.word_index += 1;
+            self.show_word();
+        } else {
+            self.end_message = true;
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Either<Task<Message>, Action> {
+        match message {
+            Message::Start(c, n, difficulty) => {
+                let keys = c.words().keys().cloned().collect::<Vec<_>>();
+                if keys.len() < n {
+                    println!("there is not enough words in collection");
+                }
+                self.words = keys
+                    .choose_multiple(&mut rand::thread_rng(), n)
+                    .cloned()
+                    .collect();
+                self.difficulty = difficulty;
+                self.confusions = store::or_warn("load confusions", |s| s.confusions(c.key()))
+                    .unwrap_or_default();
+                self.selected_collection = Some(c);
+                self.word_index = 0;
+                self.correct = 0;
+                self.save_status = None;
+                self.end_message = false;
+                self.session = Utc::now().timestamp_millis();
+                if !self.words.is_empty() {
+                    self.show_word();
+                }
+            }
+            Message::Picked(i) => self.pick(i),
+            Message::KeyPressed(Key::Named(Named::Escape)) => {
+                return Right(Action::ChangeScreen("setup_screen".into()))
+            }
+            Message::KeyPressed(Key::Named(Named::Enter)) => {
+                if self.end_message || self.words.is_empty() {
+                    return Right(Action::ChangeScreen("setup_screen".into()));
+                }
+                if self.picked.is_some() {
+                    self.next();
+                }
+            }
+            Message::KeyPressed(Key::Character(c)) => match c.parse::<usize>() {
+                Ok(n @ 1..=OPTIONS) if self.picked.is_none() => self.pick(n - 1),
+                _ if self.picked.is_some() && !self.end_message => self.next(),
+                _ => {}
+            },
+            Message::KeyPressed(_) => {}
+        }
+        Left(Task::none())
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let Some(coll) = &self.selected_collection else {
+            return container(text("Smthing not right")).center(Fill).into();
+        };
+        if self.words.is_empty() {
+            return container(text("Collection is empty")).center(Fill).into();
+        }
+        if self.end_message {
+            return container(
+                column![
+                    text("Quiz finished").size(64),
+                    text("Press ENTER to exit").size(12),
+                    text!("Score: {} / {}", self.correct, self.words.len()).size(24),
+                ]
+                .spacing(10)
+                .align_x(Center),
+            )
+            .center(Fill)
+            .into();
+        }
+        let word = &self.words[self.word_index];
+        let options = self.options.iter().enumerate().map(|(i, option)| {
+            let label = format!("{}. {}", i + 1, coll.words()[option].join(" / "));
+            let color = match self.picked {
+                Some(_) if option == word => Some(Color::new(0., 1., 0., 1.)),
+                Some(p) if p == i => Some(Color::new(1., 0., 0., 1.)),
+                _ => None,
+            };
+            container(
+                button(text(label).size(20))
+                    .width(Fill)
+                    .on_press_maybe(self.picked.is_none().then_some(Message::Picked(i))),
+            )
+            .padding(4)
+            .style(move |_| color.map_or(container::Style::default(), container::background))
+            .into()
+        });
+        container(
+            column![
+                text(word).size(48),
+                column(options).spacing(5),
+                text(if self.picked.is_some() {
+                    "Press ENTER to continue"
+                } else {
+                    "Press 1-4 to choose"
+                })
+                .size(12),
+            ]
+            .push_maybe(self.save_status.as_ref().map(|s| text(s).size(12)))
+            .spacing(30)
+            .align_x(Center)
+            .width(400),
+        )
+        .center(Fill)
+        .into()
+    }
+}
+
+impl Default for ChoiceWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
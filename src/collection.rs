@@ -10,12 +10,62 @@ use std::{
     sync::Arc,
 };
 
+use rand::seq::SliceRandom;
+
+use crate::checker::Equivalence;
+
+// which side of an entry is shown as the prompt
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Direction {
+    Forward,
+    Reverse,
+    Mixed,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 3] = [Direction::Forward, Direction::Reverse, Direction::Mixed];
+
+    // meaning to show when `word` is asked backwards, None asks for the meaning of `word`
+    pub fn reverse_prompt(&self, coll: &Collection, word: &str) -> Option<String> {
+        let reverse = match self {
+            Direction::Forward => false,
+            Direction::Reverse => true,
+            Direction::Mixed => rand::random(),
+        };
+        if !reverse {
+            return None;
+        }
+        coll.words()
+            .get(word)?
+            .choose(&mut rand::thread_rng())
+            .cloned()
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Direction::Forward => "Word → meaning",
+            Direction::Reverse => "Meaning → word",
+            Direction::Mixed => "Mixed",
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Collection {
     pub id: usize,
+    // stable name used for anything saved about the collection (file name)
+    key: String,
     lang: String,
     variables: HashMap<String, String>,
     words: HashMap<String, Vec<String>>,
+    // part of speech, topic, ... given after a second '|'
+    tags: HashMap<String, Vec<String>>,
+    // meaning -> every word that has it
+    reverse: HashMap<String, Vec<String>>,
+    // word -> (reading, example, ...) given on '>' lines after it
+    details: HashMap<String, HashMap<String, String>>,
 }
 impl PartialEq for Collection {
     fn eq(&self, other: &Self) -> bool {
@@ -27,18 +77,26 @@ impl Collection {
     pub fn new(id: usize) -> Self {
         Self {
             id,
+            key: String::new(),
             lang: String::new(),
             variables: HashMap::new(),
             words: HashMap::new(),
+            tags: HashMap::new(),
+            reverse: HashMap::new(),
+            details: HashMap::new(),
         }
     }
 
     pub fn new_from_path(file: PathBuf, id: usize) -> Result<Self> {
+        let mut coll = Collection::new(id);
+        coll.key = file
+            .file_stem()
+            .map_or(String::new(), |v| v.to_string_lossy().into_owned());
         let mut file = fs::File::open(file)?;
         let mut s = String::new();
         file.read_to_string(&mut s);
 
-        let mut coll = Collection::new(id);
+        let mut last_word: Option<String> = None;
         for (line_num, line) in s.split('\n').enumerate() {
             let linec = line.trim().chars().collect::<Vec<char>>();
             if linec.is_empty() || linec[0] == '#' {
@@ -78,22 +136,42 @@ impl Collection {
                 }
                 continue;
             }
+            if linec[0] == '>' {
+                let line = linec[1..].iter().collect::<String>();
+                match (&last_word, line.split_once('=')) {
+                    (Some(word), Some((name, value))) => {
+                        coll.details
+                            .entry(word.clone())
+                            .or_default()
+                            .insert(name.trim().to_owned(), value.trim().to_owned());
+                    }
+                    _ => println!("invalide detail |Line: {}|", line_num + 1),
+                }
+                continue;
+            }
 
             let mut word = String::new();
             let mut meaning = String::new();
             let mut meanings = Vec::new();
+            let mut tags = String::new();
             let mut after_pipe = false;
+            let mut after_second_pipe = false;
 
             for c in &linec {
                 if *c == '|' {
                     if !after_pipe {
                         after_pipe = true;
                         continue;
+                    } else if !after_second_pipe {
+                        after_second_pipe = true;
+                        continue;
                     } else {
                         println!("invalide use of next '|' |Line: {}|", line_num + 1)
                     }
                 }
-                if after_pipe {
+                if after_second_pipe {
+                    tags.push(*c);
+                } else if after_pipe {
                     if *c == '/' {
                         if !meaning.is_empty() {
                             meanings.push(meaning.trim().to_owned());
@@ -110,6 +188,17 @@ impl Collection {
                 meanings.push(meaning.trim().to_owned());
             }
             let word = word.trim();
+            let tags = tags
+                .split(',')
+                .map(|t| t.trim().to_owned())
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>();
+            if tags.is_empty() {
+                coll.tags.remove(word);
+            } else {
+                coll.tags.insert(word.to_owned(), tags);
+            }
+            last_word = Some(word.to_owned());
             let old = coll.words.insert(word.to_owned(), meanings.clone());
             if let Some(old) = old {
                 println!(
@@ -118,12 +207,60 @@ impl Collection {
                 );
             }
         }
+        for (word, meanings) in &coll.words {
+            for meaning in meanings {
+                coll.reverse
+                    .entry(meaning.clone())
+                    .or_default()
+                    .push(word.clone());
+            }
+        }
+        coll.reverse.values_mut().for_each(|words| words.sort());
         Ok(coll)
     }
 
     pub fn words(&self) -> &HashMap<String, Vec<String>> {
         &self.words
     }
+
+    // random entry, other than `previous` when there is another one
+    pub fn random_word(&self, previous: &str) -> String {
+        let words = self
+            .words
+            .keys()
+            .filter(|w| self.words.len() < 2 || *w != previous)
+            .collect::<Vec<_>>();
+        words
+            .choose(&mut rand::thread_rng())
+            .map_or(String::new(), |w| (*w).clone())
+    }
+
+    // words that have `meaning` as one of their meanings
+    pub fn words_for(&self, meaning: &str) -> &[String] {
+        self.reverse.get(meaning).map_or(&[], |v| v.as_slice())
+    }
+
+    pub fn detail(&self, word: &str, name: &str) -> Option<&str> {
+        self.details.get(word)?.get(name).map(|v| v.as_str())
+    }
+
+    pub fn tags(&self, word: &str) -> &[String] {
+        self.tags.get(word).map_or(&[], |v| v.as_slice())
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&str> {
+        self.variables.get(name).map(|v| v.as_str())
+    }
+
+    pub fn equivalences(&self) -> Vec<Equivalence> {
+        self.variable("equivalence")
+            .map(Equivalence::parse_list)
+            .unwrap_or_default()
+    }
 }
 impl Display for Collection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
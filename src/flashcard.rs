@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use either::Either::{self, Left, Right};
+use iced::{
+    keyboard::{key::Named, Key},
+    widget::{button, column, container, row, text},
+    Alignment::Center,
+    Element,
+    Length::Fill,
+    Task,
+};
+
+use crate::{
+    collection::{Collection, Direction},
+    scheduler::{Grade, Scheduler, SchedulerKind},
+    store::{self, Review},
+    Action,
+};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Start(Arc<Collection>, SchedulerKind, Direction),
+    Reveal,
+    Graded(Grade),
+    KeyPressed(Key),
+}
+
+#[derive(Debug)]
+pub struct FlashcardWidget {
+    selected_collection: Option<Arc<Collection>>,
+    words: Vec<String>,
+    word_index: usize,
+    // None cycles through the words in order
+    scheduler: Option<Box<dyn Scheduler>>,
+    direction: Direction,
+    // meaning shown instead of the current word when it is asked backwards
+    reverse: Option<String>,
+    revealed: bool,
+    // cards graded this session
+    reviewed: usize,
+    // when the current card was shown, in ms
+    shown_at: i64,
+    // when the session started, in ms
+    session: i64,
+    // last error saving to the store, shown on the screen
+    save_status: Option<String>,
+}
+
+impl FlashcardWidget {
+    pub fn new() -> Self {
+        Self {
+            selected_collection: None,
+            words: Vec::new(),
+            word_index: 0,
+            scheduler: None,
+            direction: Direction::Forward,
+            reverse: None,
+            revealed: false,
+            reviewed: 0,
+            shown_at: 0,
+            session: 0,
+            save_status: None,
+        }
+    }
+
+    fn advance(&mut self, last: Option<&str>) {
+        let next = self
+            .scheduler
+            .as_ref()
+            .and_then(|s| s.next(&self.words, last, Utc::now().timestamp()))
+            .and_then(|next| self.words.iter().position(|w| *w == next));
+        self.word_index = match next {
+            Some(i) => i,
+            None if last.is_none() => 0,
+            None => (self.word_index + 1) % self.words.len(),
+        };
+        let coll = self.selected_collection.as_ref().unwrap();
+        self.reverse = self
+            .direction
+            .reverse_prompt(coll, &self.words[self.word_index]);
+        self.revealed = false;
+        self.shown_at = Utc::now().timestamp_millis();
+    }
+
+    fn grade(&mut self, grade: Grade) {
+        if !self.revealed {
+            return;
+        }
+        let coll = self.selected_collection.clone().unwrap();
+        let word = self.words[self.word_index].clone();
+        let review = Review::graded(
+            coll.key(),
+            &word,
+            "flashcard",
+            grade,
+            self.shown_at,
+            self.session,
+        );
+        store::or_report(&mut self.save_status, "save review", |s| {
+            s.record_review(&review)
+        });
+        if let Some(scheduler) = &mut self.scheduler {
+            scheduler.review(&word, grade, Utc::now().timestamp());
+            store::report(&mut self.save_status, "save schedule", scheduler.save());
+        }
+        self.reviewed += 1;
+        self.advance(Some(&word));
+    }
+
+    pub fn update(&mut self, message: Message) -> Either<Task<Message>, Action> {
+        match message {
+            Message::Start(c, scheduler, direction) => {
+                self.words = c.words().keys().cloned().collect();
+                self.scheduler = scheduler.load(&c);
+                self.direction = direction;
+                self.selected_collection = Some(c);
+                self.reviewed = 0;
+                self.save_status = None;
+                self.session = Utc::now().timestamp_millis();
+                if !self.words.is_empty() {
+                    self.advance(None);
+                }
+            }
+            Message::Reveal => self.revealed = true,
+            Message::Graded(grade) => self.grade(grade),
+            Message::KeyPressed(Key::Named(Named::Escape)) => {
+                return Right(Action::ChangeScreen("setup_screen".into()))
+            }
+            Message::KeyPressed(Key::Named(Named::Enter | Named::Space)) => {
+                if self.words.is_empty() {
+                    return Right(Action::ChangeScreen("setup_screen".into()));
+                }
+                self.revealed = true;
+            }
+            Message::KeyPressed(Key::Character(c)) => {
+                if let Ok(n @ 1..=4) = c.parse::<usize>() {
+                    self.grade(Grade::ALL[n - 1]);
+                }
+            }
+            Message::KeyPressed(_) => {}
+        }
+        Left(Task::none())
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let Some(coll) = &self.selected_collection else {
+            return container(text("Smthing not right")).center(Fill).into();
+        };
+        if self.words.is_empty() {
+            return container(text("Collection is empty")).center(Fill).into();
+        }
+        let word = &self.words[self.word_index];
+        let prompt = self.reverse.as_deref().unwrap_or(word);
+        let bottom: Element<'_, Message> = if self.revealed {
+            let answers = match &self.reverse {
+                Some(meaning) => coll.words_for(meaning),
+                None => coll.words()[word].as_slice(),
+            };
+            column![
+                text(answers.join(" / ")).size(30),
+                row(Grade::ALL.iter().enumerate().map(|(i, grade)| {
+                    button(text!("{} {grade}", i + 1))
+                        .on_press(Message::Graded(*grade))
+                        .into()
+                }))
+                .spacing(10),
+            ]
+            .spacing(30)
+            .align_x(Center)
+            .into()
+        } else {
+            column![
+                button("Show answer").on_press(Message::Reveal),
+                text("Press SPACE or ENTER to flip").size(12),
+            ]
+            .spacing(10)
+            .align_x(Center)
+            .into()
+        };
+        container(
+            column![
+                text!("Reviewed: {}", self.reviewed).size(12),
+                text(prompt).size(48),
+                bottom,
+            ]
+            .push_maybe(self.save_status.as_ref().map(|s| text(s).size(12)))
+            .spacing(30)
+            .align_x(Center)
+            .width(400),
+        )
+        .center(Fill)
+        .into()
+    }
+}
+
+impl Default for FlashcardWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
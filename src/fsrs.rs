@@ -0,0 +1,297 @@
+// FSRS (v4.5) scheduler.
+//
+// Card stability and difficulty are rebuilt by replaying the review log,
+// so refitted parameters apply to the whole history straight away.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    scheduler::{pick_next, Grade, Scheduler, DAY, RELEARN_DELAY},
+    store::{self, Store},
+};
+
+const DECAY: f64 = -0.5;
+const FACTOR: f64 = 19. / 81.;
+const DESIRED_RETENTION: f64 = 0.9;
+
+pub const DEFAULT_PARAMS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072,
+    0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+
+// (min, max) for every parameter while fitting
+const PARAM_BOUNDS: [(f64, f64); 17] = [
+    (0.1, 100.),
+    (0.1, 100.),
+    (0.1, 100.),
+    (0.1, 100.),
+    (1., 10.),
+    (0.1, 5.),
+    (0.1, 5.),
+    (0., 0.5),
+    (0., 3.),
+    (0.1, 0.8),
+    (0.01, 2.5),
+    (0.5, 5.),
+    (0.01, 0.2),
+    (0.01, 0.9),
+    (0.01, 2.),
+    (0., 1.),
+    (1., 4.),
+];
+
+fn grade_value(grade: Grade) -> f64 {
+    match grade {
+        Grade::Again => 1.,
+        Grade::Hard => 2.,
+        Grade::Good => 3.,
+        Grade::Easy => 4.,
+    }
+}
+
+// probability of recalling a card `elapsed` days after the last review
+fn retrievability(elapsed: f64, stability: f64) -> f64 {
+    (1. + FACTOR * elapsed / stability).powf(DECAY)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Card {
+    stability: f64,
+    difficulty: f64,
+    last_review: i64,
+    due: i64,
+}
+
+fn initial_difficulty(w: &[f64; 17], g: f64) -> f64 {
+    (w[4] - (g - 3.) * w[5]).clamp(1., 10.)
+}
+
+fn step(w: &[f64; 17], card: Option<Card>, grade: Grade, now: i64) -> Card {
+    let g = grade_value(grade);
+    let (stability, difficulty) = match card {
+        None => (w[g as usize - 1], initial_difficulty(w, g)),
+        Some(c) => {
+            let elapsed = (now - c.last_review).max(0) as f64 / DAY as f64;
+            let r = retrievability(elapsed, c.stability);
+            let d = c.difficulty - w[6] * (g - 3.);
+            let d = (w[7] * initial_difficulty(w, 3.) + (1. - w[7]) * d).clamp(1., 10.);
+            let s = if grade == Grade::Again {
+                w[11]
+                    * c.difficulty.powf(-w[12])
+                    * ((c.stability + 1.).powf(w[13]) - 1.)
+                    * (w[14] * (1. - r)).exp()
+            } else {
+                let hard = if grade == Grade::Hard { w[15] } else { 1. };
+                let easy = if grade == Grade::Easy { w[16] } else { 1. };
+                c.stability
+                    * (w[8].exp()
+                        * (11. - c.difficulty)
+                        * c.stability.powf(-w[9])
+                        * ((w[10] * (1. - r)).exp() - 1.)
+                        * hard
+                        * easy
+                        + 1.)
+            };
+            (s.max(0.01), d)
+        }
+    };
+    let due = if grade == Grade::Again {
+        now + RELEARN_DELAY
+    } else {
+        let interval = stability / FACTOR * (DESIRED_RETENTION.powf(1. / DECAY) - 1.);
+        now + (interval.max(1.) * DAY as f64) as i64
+    };
+    Card {
+        stability,
+        difficulty,
+        last_review: now,
+        due,
+    }
+}
+
+#[derive(Debug)]
+pub struct Fsrs {
+    collection: String,
+    params: [f64; 17],
+    cards: HashMap<String, Card>,
+}
+
+impl Fsrs {
+    // saved as a space separated list in the settings
+    pub fn load_params() -> [f64; 17] {
+        let saved = store::or_warn("load FSRS parameters", |s| s.setting("fsrs_params"));
+        let Some(Some(s)) = saved else {
+            return DEFAULT_PARAMS;
+        };
+        let params = s
+            .split_whitespace()
+            .filter_map(|v| v.parse::<f64>().ok())
+            .collect::<Vec<_>>();
+        params.try_into().unwrap_or(DEFAULT_PARAMS)
+    }
+
+    pub fn save_params(params: &[f64; 17]) -> Result<()> {
+        let s = params.map(|v| v.to_string()).join(" ");
+        store::with_default(|st| st.set_setting("fsrs_params", &s))
+    }
+
+    pub fn load(collection: &str) -> Self {
+        let params = Self::load_params();
+        let mut cards = HashMap::new();
+        let reviews = store::or_warn("load review log", |s| s.reviews(Some(collection)));
+        for r in reviews.unwrap_or_default() {
+            let grade = Grade::from_outcome(&r.outcome).with_hints(r.hints);
+            let card = step(&params, cards.get(&r.entry).copied(), grade, r.timestamp);
+            cards.insert(r.entry, card);
+        }
+        Self {
+            collection: collection.to_owned(),
+            params,
+            cards,
+        }
+    }
+
+    // refits the parameters to the whole review log and saves them
+    pub fn fit() -> Result<[f64; 17]> {
+        let mut histories: HashMap<(String, String), Vec<(Grade, i64)>> = HashMap::new();
+        for r in store::with_default(|s| s.reviews(None))? {
+            histories.entry((r.collection, r.entry)).or_default().push((
+                Grade::from_outcome(&r.outcome).with_hints(r.hints),
+                r.timestamp,
+            ));
+        }
+        let histories = histories.into_values().collect::<Vec<_>>();
+        if loss(&DEFAULT_PARAMS, &histories).is_none() {
+            return Err(anyhow!("not enough reviews spanning more than a day"));
+        }
+
+        let params = optimise(Self::load_params(), &histories);
+        Self::save_params(&params)?;
+        Ok(params)
+    }
+}
+
+// mean log loss of predicted recall over reviews at least a day after the previous one
+fn loss(params: &[f64; 17], histories: &[Vec<(Grade, i64)>]) -> Option<f64> {
+    let mut total = 0.;
+    let mut count = 0;
+    for history in histories {
+        let mut card: Option<Card> = None;
+        for (grade, ts) in history {
+            if let Some(c) = card {
+                let elapsed = (ts - c.last_review) as f64 / DAY as f64;
+                if elapsed >= 1. {
+                    let r = retrievability(elapsed, c.stability).clamp(1e-6, 1. - 1e-6);
+                    let recalled = *grade != Grade::Again;
+                    total -= if recalled { r.ln() } else { (1. - r).ln() };
+                    count += 1;
+                }
+            }
+            card = Some(step(params, card, *grade, *ts));
+        }
+    }
+    (count > 0).then(|| total / count as f64)
+}
+
+// gradient descent from `params`, only keeping steps that lower the loss
+fn optimise(mut params: [f64; 17], histories: &[Vec<(Grade, i64)>]) -> [f64; 17] {
+    let mut best = loss(&params, histories).unwrap();
+    let mut lr = 0.05;
+    for _ in 0..200 {
+        let mut grad = [0.; 17];
+        for (i, g) in grad.iter_mut().enumerate() {
+            let eps = (PARAM_BOUNDS[i].1 - PARAM_BOUNDS[i].0) * 1e-4;
+            let mut shifted = params;
+            shifted[i] = (params[i] + eps).min(PARAM_BOUNDS[i].1);
+            *g = (loss(&shifted, histories).unwrap() - best) / (shifted[i] - params[i]).max(1e-12);
+        }
+        let mut candidate = params;
+        for (i, (min, max)) in PARAM_BOUNDS.iter().enumerate() {
+            candidate[i] =
+                (params[i] - lr * (max - min) * grad[i].clamp(-1., 1.)).clamp(*min, *max);
+        }
+        let candidate_loss = loss(&candidate, histories).unwrap();
+        if candidate_loss < best {
+            params = candidate;
+            best = candidate_loss;
+        } else {
+            lr /= 2.;
+            if lr < 1e-5 {
+                break;
+            }
+        }
+    }
+    params
+}
+
+impl Scheduler for Fsrs {
+    fn review(&mut self, word: &str, grade: Grade, now: i64) {
+        let card = step(&self.params, self.cards.get(word).copied(), grade, now);
+        self.cards.insert(word.to_owned(), card);
+    }
+
+    fn next(&self, words: &[String], last: Option<&str>, now: i64) -> Option<String> {
+        pick_next(words, last, now, |w| self.cards.get(w).map(|c| c.due))
+    }
+
+    // the review log is the source of truth, this only keeps due dates queryable
+    fn save(&mut self) -> Result<()> {
+        let cards = self.cards.iter().map(|(word, c)| {
+            let state = format!("{} {}", c.stability, c.difficulty);
+            (word.as_str(), state, c.due)
+        });
+        store::with_default(|s| s.save_cards("fsrs", &self.collection, cards))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_review_and_lapse() {
+        let card = step(&DEFAULT_PARAMS, None, Grade::Good, 0);
+        let days = card.due as f64 / DAY as f64;
+        assert!((days - DEFAULT_PARAMS[2]).abs() < 0.01);
+        let card = step(&DEFAULT_PARAMS, Some(card), Grade::Again, card.due);
+        assert_eq!(card.due - card.last_review, RELEARN_DELAY);
+        assert!(card.stability < DEFAULT_PARAMS[2]);
+    }
+
+    #[test]
+    fn loss_needs_a_review_a_day_later() {
+        let same_day = vec![vec![(Grade::Good, 0), (Grade::Good, DAY / 2)]];
+        assert!(loss(&DEFAULT_PARAMS, &same_day).is_none());
+        let next_day = vec![vec![(Grade::Good, 0), (Grade::Good, DAY)]];
+        assert!(loss(&DEFAULT_PARAMS, &next_day).is_some());
+    }
+
+    #[test]
+    fn fitting_never_raises_the_loss() {
+        let histories = vec![
+            vec![
+                (Grade::Good, 0),
+                (Grade::Good, 4 * DAY),
+                (Grade::Good, 20 * DAY),
+            ],
+            vec![
+                (Grade::Again, 0),
+                (Grade::Again, 2 * DAY),
+                (Grade::Good, 3 * DAY),
+            ],
+            vec![
+                (Grade::Easy, 0),
+                (Grade::Hard, 10 * DAY),
+                (Grade::Again, 30 * DAY),
+            ],
+        ];
+        let before = loss(&DEFAULT_PARAMS, &histories).unwrap();
+        let params = optimise(DEFAULT_PARAMS, &histories);
+        assert!(loss(&params, &histories).unwrap() <= before);
+        for (v, (min, max)) in params.iter().zip(PARAM_BOUNDS) {
+            assert!((min..=max).contains(v));
+        }
+    }
+}
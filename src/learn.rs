@@ -1,39 +1,95 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
+use chrono::Utc;
 use either::Either::{self, Left, Right};
 use iced::{
     keyboard::key::Named,
-    widget::{
-        button::{self},
-        column, container, text, text_input, TextInput,
-    },
+    widget::{button, column, container, row, text, text_input, TextInput},
     Alignment::Center,
     Background, Border, Color, Element,
     Length::Fill,
     Task,
 };
 
-use crate::{collection::Collection, Action, KeyAcceptor};
+use crate::{
+    checker::{self, AnswerChecker, DiffPart, Verdict},
+    collection::{Collection, Direction},
+    scheduler::{Grade, Scheduler, SchedulerKind},
+    store::{self, DailyGoals, DayProgress, Review},
+    Action, KeyAcceptor,
+};
 
 #[derive(Debug, Clone)]
 pub enum Message {
     InputChanged(String),
     InputSubmit,
-    Start(Arc<Collection>),
+    Start(Arc<Collection>, LearnOptions),
     KeyPressed(Named),
+    NoteChanged(String),
+    HintButton,
+}
+
+// how a learn session is run, picked on the setup screen
+#[derive(Debug, Clone, Copy)]
+pub struct LearnOptions {
+    pub require_all: bool,
+    pub scheduler: SchedulerKind,
+    pub direction: Direction,
+    pub goals: DailyGoals,
+    // the session ends once today's goals are reached
+    pub end_at_goal: bool,
 }
 
 #[derive(Debug, Clone)]
 enum Answer {
-    Correct,
-    Incorrect,
+    Checked {
+        verdict: Verdict,
+        word: String,
+        input: String,
+        meanings: Vec<String>,
+    },
     None,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct LearnWidget {
     selected_collection: Option<Arc<Collection>>,
     selected_collection_words: Option<Vec<String>>,
+    checker: AnswerChecker,
+    // None cycles through the words in order
+    scheduler: Option<Box<dyn Scheduler>>,
+    // name of the mode in the review log
+    mode: &'static str,
+    direction: Direction,
+    // meaning shown instead of the current word when it is asked backwards
+    reverse: Option<String>,
+    // hints asked for the current word
+    hints: usize,
+    // words introduced or answered before
+    seen: HashSet<String>,
+    goals: DailyGoals,
+    // the session ends once today's goals are reached
+    end_at_goal: bool,
+    // today's progress, this session included
+    today: DayProgress,
+    // answers, correct answers and new words of this session
+    answered: usize,
+    correct: usize,
+    introduced: usize,
+    // the session summary is shown
+    finished: bool,
+    // the current word is new and shown before it is asked
+    introducing: bool,
+    // every seen word is done and no new word can be introduced today
+    out_of_words: bool,
+    // when the current word was shown, in ms
+    shown_at: i64,
+    // when the session started, in ms
+    session: i64,
+    // last error saving to the store, shown on the screen
+    save_status: Option<String>,
+    // user note of the word shown in the feedback
+    note: String,
     answer: Answer,
     word_index: usize,
     inputed: String,
@@ -46,8 +102,83 @@ impl LearnWidget {
             answer: Answer::None,
             inputed: String::new(),
             selected_collection_words: None,
+            checker: AnswerChecker::new(),
+            scheduler: None,
+            mode: "learn",
+            direction: Direction::Forward,
+            reverse: None,
+            hints: 0,
+            seen: HashSet::new(),
+            goals: DailyGoals {
+                new_words: 0,
+                reviews: 0,
+                minutes: 0,
+            },
+            end_at_goal: false,
+            today: DayProgress::default(),
+            answered: 0,
+            correct: 0,
+            introduced: 0,
+            finished: false,
+            introducing: false,
+            out_of_words: false,
+            shown_at: 0,
+            session: 0,
+            note: String::new(),
+            save_status: None,
+        }
+    }
+
+    fn advance(&mut self, last: Option<&str>) {
+        let words = self.selected_collection_words.as_ref().unwrap();
+        // words never seen only come in while today's introductions last
+        let new_left = self.today.new_words < self.goals.new_words.into();
+        let pool = words
+            .iter()
+            .filter(|w| new_left || self.seen.contains(*w))
+            .cloned()
+            .collect::<Vec<_>>();
+        if pool.is_empty() {
+            self.out_of_words = true;
+            return;
+        }
+        let next = self
+            .scheduler
+            .as_ref()
+            .and_then(|s| s.next(&pool, last, Utc::now().timestamp()))
+            .and_then(|next| words.iter().position(|w| *w == next));
+        let start = if last.is_none() {
+            0
+        } else {
+            self.word_index + 1
+        };
+        self.word_index = next.unwrap_or_else(|| {
+            (0..words.len())
+                .map(|i| (start + i) % words.len())
+                .find(|i| pool.contains(&words[*i]))
+                .unwrap()
+        });
+        let word = words[self.word_index].clone();
+        let coll = self.selected_collection.as_ref().unwrap();
+        self.reverse = self.direction.reverse_prompt(coll, &word);
+        self.hints = 0;
+        // only counted once the introduction was actually shown
+        self.introducing = !self.seen.contains(&word);
+    }
+
+    fn hint(&self) -> Option<String> {
+        if self.hints == 0 {
+            return None;
         }
+        let coll = self.selected_collection.as_ref()?;
+        let word = &self.selected_collection_words.as_ref()?[self.word_index];
+        let answer = match &self.reverse {
+            Some(meaning) => coll.words_for(meaning).first(),
+            None => coll.words().get(word)?.first(),
+        }?;
+        Some(checker::hint(answer, self.hints))
     }
+
     pub fn update(&mut self, message: Message) -> Either<Task<Message>, Action> {
         match message {
             Message::InputChanged(input) => self.inputed = input,
@@ -62,54 +193,199 @@ impl LearnWidget {
                     .get(self.word_index)
                     // .map(|v| v.as_str())
                     .unwrap();
-                let is_correct = self
-                    .selected_collection
-                    .clone()
-                    .unwrap()
-                    .words()
-                    .get(word)
-                    .unwrap()
-                    .contains(&self.inputed);
-
-                if is_correct {
-                    self.answer = Answer::Correct;
-                } else {
-                    self.answer = Answer::Incorrect;
+                let coll = self.selected_collection.clone().unwrap();
+                let (verdict, meanings) = match &self.reverse {
+                    Some(meaning) => (
+                        self.checker.check_meaning(&coll, meaning, &self.inputed),
+                        coll.words_for(meaning),
+                    ),
+                    None => (
+                        self.checker.check_entry(&coll, word, &self.inputed),
+                        coll.words().get(word).unwrap().as_slice(),
+                    ),
+                };
+                let review = Review::new(
+                    coll.key(),
+                    word,
+                    self.mode,
+                    &self.inputed,
+                    &verdict,
+                    self.shown_at,
+                    self.session,
+                )
+                .with_hints(self.hints);
+                store::or_report(&mut self.save_status, "save review", |s| {
+                    s.record_answer(&review, &verdict)
+                });
+                self.answered += 1;
+                self.correct += usize::from(verdict.is_correct());
+                self.today.reviews += 1;
+                self.today.ms += review.response_ms.min(60_000);
+                self.finished = self.end_at_goal && self.today.reached(&self.goals);
+                self.note = store::or_warn("load note", |s| s.note(coll.key(), word))
+                    .flatten()
+                    .unwrap_or_default();
+                if let Some(scheduler) = &mut self.scheduler {
+                    let grade = Grade::from(&verdict).with_hints(self.hints);
+                    scheduler.review(word, grade, Utc::now().timestamp());
+                    store::report(&mut self.save_status, "save schedule", scheduler.save());
                 }
-                if self.word_index < self.selected_collection_words.as_ref().unwrap().len() - 1 {
-                    self.word_index += 1
-                } else {
-                    self.word_index = 0;
+                let word = word.clone();
+                self.answer = Answer::Checked {
+                    verdict,
+                    word: word.clone(),
+                    input: self.inputed.clone(),
+                    meanings: meanings.to_vec(),
+                };
+                if !self.finished {
+                    self.advance(Some(&word));
                 }
                 self.inputed.clear();
             }
-            Message::Start(c) => {
+            Message::Start(
+                c,
+                LearnOptions {
+                    require_all,
+                    scheduler,
+                    direction,
+                    goals,
+                    end_at_goal,
+                },
+            ) => {
                 self.selected_collection = Some(c.clone());
+                self.checker = AnswerChecker::for_session(&c, require_all);
+                self.scheduler = scheduler.load(&c);
+                self.direction = direction;
+                self.mode = match scheduler {
+                    SchedulerKind::Leitner => "leitner",
+                    _ => "learn",
+                };
+                self.shown_at = Utc::now().timestamp_millis();
+                self.session = self.shown_at;
                 self.answer = Answer::None;
                 self.inputed.clear();
-                self.word_index = 0;
+                self.seen =
+                    store::or_warn("load history", |s| s.seen_entries(c.key())).unwrap_or_default();
+                self.today = store::or_warn("load progress", |s| s.today()).unwrap_or_default();
+                self.goals = goals;
+                self.end_at_goal = end_at_goal;
+                self.answered = 0;
+                self.save_status = None;
+                self.correct = 0;
+                self.introduced = 0;
+                self.finished = end_at_goal && self.today.reached(&goals);
+                self.out_of_words = false;
                 self.selected_collection_words =
-                    Some(c.words().keys().map(|v| v.to_owned()).collect())
+                    Some(c.words().keys().map(|v| v.to_owned()).collect());
+                if !self.finished {
+                    self.advance(None);
+                }
+            }
+            Message::NoteChanged(note) => {
+                if let Answer::Checked { word, .. } = &self.answer {
+                    let coll = self.selected_collection.clone().unwrap();
+                    store::or_report(&mut self.save_status, "save note", |s| {
+                        s.set_note(coll.key(), word, &note)
+                    });
+                }
+                self.note = note;
             }
             Message::KeyPressed(k) => match k {
+                // the first ESC ends the session with a summary
+                Named::Escape if !self.finished && self.answered > 0 => {
+                    self.finished = true;
+                    self.answer = Answer::None;
+                }
                 Named::Escape => return Right(Action::ChangeScreen("setup_screen".into())),
                 Named::Enter => match self.answer {
-                    Answer::Correct | Answer::Incorrect => {
+                    Answer::Checked { .. } if self.finished => self.answer = Answer::None,
+                    Answer::None if self.finished => {
+                        return Right(Action::ChangeScreen("setup_screen".into()))
+                    }
+                    Answer::Checked { .. } => {
                         self.answer = Answer::None;
+                        self.shown_at = Utc::now().timestamp_millis();
+                        return Left(text_input::focus("learn_input_id"));
+                    }
+                    Answer::None if self.introducing => {
+                        self.introducing = false;
+                        let coll = self.selected_collection.clone().unwrap();
+                        let word = self.selected_collection_words.as_ref().unwrap()
+                            [self.word_index]
+                            .clone();
+                        self.today.new_words += 1;
+                        self.introduced += 1;
+                        store::or_report(&mut self.save_status, "save introduction", |s| {
+                            s.introduce(coll.key(), &word)
+                        });
+                        self.seen.insert(word);
+                        self.shown_at = Utc::now().timestamp_millis();
                         return Left(text_input::focus("learn_input_id"));
                     }
                     Answer::None => (),
                 },
+                Named::Tab => return self.update(Message::HintButton),
                 _ => {}
             },
+            Message::HintButton => {
+                if matches!(self.answer, Answer::None) && !self.introducing {
+                    self.hints = (self.hints + 1).min(checker::MAX_HINTS);
+                    return Left(text_input::focus("learn_input_id"));
+                }
+            }
         }
         Left(Task::none())
     }
 
-    pub fn view(&self) -> Element<Message> {
+    fn summary_view(&self) -> Element<'_, Message> {
+        let title = if self.today.reached(&self.goals) {
+            "Daily goal reached"
+        } else {
+            "Session over"
+        };
+        let minutes = (Utc::now().timestamp_millis() - self.session) / 60_000;
+        container(
+            column![
+                text(title).size(64),
+                text("Press ENTER to exit").size(12),
+                text!(
+                    "Answers: {}, correct: {}%",
+                    self.answered,
+                    (self.correct * 100).checked_div(self.answered).unwrap_or(0)
+                )
+                .size(24),
+                text!("New words: {}, time: {minutes} min", self.introduced).size(24),
+                text!("Today: {}", self.today.describe(&self.goals)).size(20),
+            ]
+            .spacing(10)
+            .align_x(Center),
+        )
+        .center(Fill)
+        .into()
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
         if self.selected_collection.is_none() {
             return container(text("Smthing not right")).center(Fill).into();
         }
+        if self.finished && matches!(self.answer, Answer::None) {
+            return self.summary_view();
+        }
+        if self.out_of_words {
+            let words = self.selected_collection_words.as_ref().unwrap();
+            let message = if words.is_empty() {
+                "Collection is empty"
+            } else {
+                "No new words left for today"
+            };
+            return container(
+                column![text(message).size(30), text("Press ESC to exit").size(12)]
+                    .spacing(10)
+                    .align_x(Center),
+            )
+            .center(Fill)
+            .into();
+        }
         let word = self
             .selected_collection_words
             .as_ref()
@@ -117,33 +393,200 @@ impl LearnWidget {
             .get(self.word_index)
             .map(|v| v.as_str())
             .unwrap();
-        match self.answer {
-            Answer::Correct => container(text("Correct").size(60))
+        let feedback: Element<'_, Message> = match &self.answer {
+            Answer::Checked {
+                verdict: Verdict::Correct,
+                ..
+            } => container(text("Correct").size(60))
                 .center(Fill)
                 .style(|v| container::background(Color::new(0., 1., 0., 1.)))
                 .into(),
-            Answer::Incorrect => container(text("Incorrect").size(60))
-                .center(Fill)
-                .style(|v| container::background(Color::new(1., 0., 0., 1.)))
-                .into(),
-            Answer::None => container(
+            Answer::Checked {
+                verdict: Verdict::Variant,
+                ..
+            } => container(
                 column![
-                    text(word).size(48),
-                    text_input("Type...", &self.inputed)
-                        .id("learn_input_id")
-                        .on_input(Message::InputChanged)
-                        .on_submit(Message::InputSubmit),
+                    text("Correct").size(60),
+                    text("accepted with variant").size(20)
                 ]
-                .spacing(30)
-                .align_x(Center)
-                .width(400),
+                .align_x(Center),
             )
             .center(Fill)
+            .style(|v| container::background(Color::new(0., 1., 0., 1.)))
             .into(),
-        }
+            Answer::Checked {
+                verdict: Verdict::Almost { expected, missing },
+                ..
+            } => container(
+                column![
+                    text("Almost").size(60),
+                    text(expected).size(30),
+                    text!("missing: {}", checker::join_chars(missing)).size(20)
+                ]
+                .align_x(Center),
+            )
+            .center(Fill)
+            .style(|v| container::background(Color::new(1., 0.65, 0., 1.)))
+            .into(),
+            Answer::Checked {
+                verdict: Verdict::Typo { expected, .. },
+                input,
+                ..
+            } => container(
+                column![
+                    text("Typo").size(60),
+                    text(expected).size(30),
+                    diff_view(expected, input)
+                ]
+                .spacing(10)
+                .align_x(Center),
+            )
+            .center(Fill)
+            .style(|v| container::background(Color::new(1., 0.65, 0., 1.)))
+            .into(),
+            Answer::Checked {
+                verdict:
+                    verdict @ Verdict::Partial {
+                        given,
+                        typos,
+                        missed,
+                        wrong,
+                    },
+                word,
+                ..
+            } => container(
+                column![
+                    text(verdict.label()).size(60),
+                    text(word).size(30),
+                    text!("Given: {}", given.join(" / "))
+                        .size(20)
+                        .color(Color::new(0., 0.4, 0., 1.)),
+                    text!("Missed: {}", missed.join(" / ")).size(20),
+                ]
+                .push_maybe(
+                    (!typos.is_empty())
+                        .then(|| text!("With a typo: {}", typos.join(" / ")).size(20)),
+                )
+                .push_maybe(
+                    (!wrong.is_empty()).then(|| text!("Wrong: {}", wrong.join(" / ")).size(20)),
+                )
+                .spacing(10)
+                .align_x(Center),
+            )
+            .center(Fill)
+            .style(|v| container::background(Color::new(1., 0.65, 0., 1.)))
+            .into(),
+            Answer::Checked {
+                verdict: Verdict::Confused { other },
+                word,
+                input,
+                meanings,
+            } => self.incorrect_view(word, input, meanings, Some(other)),
+            Answer::Checked {
+                verdict: Verdict::Incorrect,
+                word,
+                input,
+                meanings,
+            } => self.incorrect_view(word, input, meanings, None),
+            Answer::None if self.introducing => {
+                let coll = self.selected_collection.as_ref().unwrap();
+                let details = ["reading", "example"]
+                    .into_iter()
+                    .filter_map(|name| coll.detail(word, name))
+                    .map(|value| text(value).size(20).into());
+                return container(
+                    column![
+                        text("New word").size(20),
+                        text(word).size(48),
+                        text(coll.words()[word].join(" / ")).size(30),
+                        column(details).spacing(5).align_x(Center),
+                        text("Press ENTER to start practising").size(12),
+                    ]
+                    .spacing(20)
+                    .align_x(Center)
+                    .width(400),
+                )
+                .center(Fill)
+                .into();
+            }
+            Answer::None => {
+                return container(
+                    column![
+                        text(self.reverse.as_deref().unwrap_or(word)).size(48),
+                        text_input("Type...", &self.inputed)
+                            .id("learn_input_id")
+                            .on_input(Message::InputChanged)
+                            .on_submit(Message::InputSubmit),
+                        row![button("Hint (Tab)").on_press_maybe(
+                            (self.hints < checker::MAX_HINTS).then_some(Message::HintButton)
+                        )]
+                        .push_maybe(self.hint().map(|h| text(h).size(20)))
+                        .spacing(10)
+                        .align_y(Center),
+                    ]
+                    .push_maybe(self.save_status.as_ref().map(|s| text(s).size(12)))
+                    .spacing(30)
+                    .align_x(Center)
+                    .width(400),
+                )
+                .center(Fill)
+                .into()
+            }
+        };
+        column![
+            feedback,
+            container(
+                text_input("Note for this phrase...", &self.note).on_input(Message::NoteChanged)
+            )
+            .padding(10)
+        ]
+        .push_maybe(self.save_status.as_ref().map(|s| text(s).size(12)))
+        .into()
+    }
+
+    // shared by wrong answers and answers meaning another entry
+    fn incorrect_view<'a>(
+        &self,
+        word: &'a str,
+        input: &'a str,
+        meanings: &'a [String],
+        other: Option<&'a String>,
+    ) -> Element<'a, Message> {
+        let closest = self.checker.closest(meanings, input);
+        container(
+            column![
+                text("Incorrect").size(60),
+                text(word).size(30),
+                text!("Your answer: {input}").size(20),
+            ]
+            .push_maybe(other.map(|other| text!("That's the meaning of {other}").size(20)))
+            .push(text!("Accepted: {}", meanings.join(" / ")).size(20))
+            .push_maybe(closest.map(|c| diff_view(c, input)))
+            .spacing(10)
+            .align_x(Center),
+        )
+        .center(Fill)
+        .style(|v| container::background(Color::new(1., 0., 0., 1.)))
+        .into()
     }
 }
 
+// expected answer with missing letters in green and extra typed letters in red
+fn diff_view<'a>(expected: &str, input: &str) -> Element<'a, Message> {
+    let parts = checker::diff(expected, input).into_iter().map(|part| {
+        match part {
+            DiffPart::Same(s) => text(s).size(30).color(Color::BLACK),
+            DiffPart::Missing(s) => text(s).size(30).color(Color::new(0., 0.6, 0., 1.)),
+            DiffPart::Extra(s) => text(s).size(30).color(Color::new(0.8, 0., 0., 1.)),
+        }
+        .into()
+    });
+    container(row(parts))
+        .padding(10)
+        .style(|v| container::background(Color::WHITE))
+        .into()
+}
+
 impl Default for LearnWidget {
     fn default() -> Self {
         Self::new()
@@ -1,8 +1,18 @@
 #![allow(dead_code, unused)]
+mod checker;
+mod choice;
 mod collection;
+mod flashcard;
+mod fsrs;
 mod learn;
+mod matching;
 mod modal;
+mod scheduler;
 mod setup;
+mod sprint;
+mod stats;
+mod store;
+mod survival;
 mod testing;
 
 use std::{
@@ -14,8 +24,10 @@ use std::{
     thread,
 };
 
-use collection::Collection;
+use choice::ChoiceWidget;
+use collection::{Collection, Direction};
 use either::Either::{Left, Right};
+use flashcard::FlashcardWidget;
 use iced::{
     keyboard::{key::Named, Key},
     widget::{
@@ -27,18 +39,31 @@ use iced::{
     Length::{self, Fill},
     Subscription, Task,
 };
-use learn::LearnWidget;
+use learn::{LearnOptions, LearnWidget};
+use matching::MatchingWidget;
 use modal::modal_base;
 use rfd::{AsyncFileDialog, FileDialog};
+use scheduler::SchedulerKind;
 use setup::SetupWidget;
+use sprint::SprintWidget;
+use stats::StatsWidget;
+use survival::SurvivalWidget;
 use testing::TestWidget;
 use walkdir::WalkDir;
 
 // @ lang(en,pl, ja, kr, so on)
 // $ name=name_value //only required variable will be displayed in a gui
+// $ equivalence=kana, width, han, ß=ss // optional, script differences accepted as correct (checker.rs, han covers common characters only)
+// $ normalise=nfc, trim, whitespace, case, punctuation // optional, how answers are cleaned before comparing
+// $ accents=strict | accept | almost // optional, how answers with missing diacritics are graded
+// $ typos=2, 5 // optional, up to 2 edits (one per full 5 letters) are graded as a typo instead of wrong
+// $ require_all=true // optional, every meaning has to be typed, separated by `$ separator` (default ',')
+// $ leitner=1, 2, 4, 8, 16 // optional, number of Leitner boxes and how many days between reviews of each
 // # - first in line indicates that the whole line is comment
 // word | meaning1 / meaning2/.../ meaning_n // white spaces around word will be removed
 // word2 | meaning1 / meaning2/.../ meaning_n // white spaces around meaning will be remowed.
+// word3 | meaning1 / meaning2 | noun, food // optional tags after a second '|', used to pick similar multiple choice options
+// > reading = ねこ // optional details of the word above, `reading` and `example` are shown when it is introduced
 //
 //
 //
@@ -58,14 +83,30 @@ pub enum AppScreen {
     LearnScreen(LearnWidget),
     SetupScreen(SetupWidget),
     TestingScreen(TestWidget),
+    StatsScreen(StatsWidget),
+    ChoiceScreen(ChoiceWidget),
+    FlashcardScreen(FlashcardWidget),
+    MatchingScreen(MatchingWidget),
+    SprintScreen(SprintWidget),
+    SurvivalScreen(SurvivalWidget),
     None,
 }
 #[derive(Debug)]
 pub enum Action {
     ChangeScreen(String),
     // SendColections(Vec<Arc<Collection>>),
-    StartLearnMode(Arc<Collection>),
-    StartTestMode(Arc<Collection>, usize),
+    StartLearnMode(Arc<Collection>, LearnOptions),
+    StartTestMode(Arc<Collection>, usize, bool, Direction),
+    // test session over the given entries
+    StartReview(Arc<Collection>, Vec<String>, bool, Direction),
+    ShowStats(Arc<Collection>),
+    // number of words, distractor difficulty
+    StartChoiceMode(Arc<Collection>, usize, u8),
+    StartFlashcards(Arc<Collection>, SchedulerKind, Direction),
+    StartMatching(Arc<Collection>, usize),
+    StartSprint(Arc<Collection>, bool),
+    // collection, require all meanings, profile
+    StartSurvival(Arc<Collection>, bool, String),
 }
 
 #[derive(Debug)]
@@ -73,7 +114,13 @@ pub enum Message {
     SetupMessage(String, setup::Message),
     LearnMessage(String, learn::Message),
     TestingMessage(String, testing::Message),
-    KeyPressed(Named),
+    StatsMessage(String, stats::Message),
+    ChoiceMessage(String, choice::Message),
+    FlashcardMessage(String, flashcard::Message),
+    MatchingMessage(String, matching::Message),
+    SprintMessage(String, sprint::Message),
+    SurvivalMessage(String, survival::Message),
+    KeyPressed(Key),
 }
 
 #[derive(Debug)]
@@ -98,6 +145,30 @@ impl Default for App {
             "testing_screen".into(),
             AppScreen::TestingScreen(TestWidget::new()),
         );
+        screens.insert(
+            "stats_screen".into(),
+            AppScreen::StatsScreen(StatsWidget::new()),
+        );
+        screens.insert(
+            "choice_screen".into(),
+            AppScreen::ChoiceScreen(ChoiceWidget::new()),
+        );
+        screens.insert(
+            "flashcard_screen".into(),
+            AppScreen::FlashcardScreen(FlashcardWidget::new()),
+        );
+        screens.insert(
+            "matching_screen".into(),
+            AppScreen::MatchingScreen(MatchingWidget::new()),
+        );
+        screens.insert(
+            "sprint_screen".into(),
+            AppScreen::SprintScreen(SprintWidget::new()),
+        );
+        screens.insert(
+            "survival_screen".into(),
+            AppScreen::SurvivalScreen(SurvivalWidget::new()),
+        );
         Self {
             screens,
             active_screen: "setup_screen".into(),
@@ -109,22 +180,84 @@ impl Default for App {
 impl App {
     fn perform_action(&mut self, action: Action) {
         match action {
-            Action::ChangeScreen(screen_name) => self.active_screen = screen_name,
+            Action::ChangeScreen(screen_name) => {
+                if let Some(AppScreen::SetupScreen(ss)) = self.screens.get_mut(&screen_name) {
+                    ss.update(setup::Message::Refresh);
+                }
+                self.active_screen = screen_name
+            }
             // Action::SendColections(colls) => self.collections = colls,
-            Action::StartLearnMode(coll) => {
+            Action::StartLearnMode(coll, options) => {
                 if let AppScreen::LearnScreen(ls) = self.screens.get_mut("learn_screen").unwrap() {
-                    ls.update(learn::Message::Start(coll));
+                    ls.update(learn::Message::Start(coll, options));
                 };
                 self.active_screen = "learn_screen".into()
             }
-            Action::StartTestMode(coll, num) => {
+            Action::StartTestMode(coll, num, require_all, direction) => {
                 if let AppScreen::TestingScreen(ls) =
                     self.screens.get_mut("testing_screen").unwrap()
                 {
-                    ls.update(testing::Message::Start(coll, num));
+                    ls.update(testing::Message::Start(coll, num, require_all, direction));
                 };
                 self.active_screen = "testing_screen".into()
             }
+            Action::StartReview(coll, words, require_all, direction) => {
+                if let AppScreen::TestingScreen(ls) =
+                    self.screens.get_mut("testing_screen").unwrap()
+                {
+                    ls.update(testing::Message::StartWith(
+                        coll,
+                        words,
+                        require_all,
+                        direction,
+                    ));
+                };
+                self.active_screen = "testing_screen".into()
+            }
+            Action::ShowStats(coll) => {
+                if let AppScreen::StatsScreen(ss) = self.screens.get_mut("stats_screen").unwrap() {
+                    ss.update(stats::Message::Start(coll));
+                };
+                self.active_screen = "stats_screen".into()
+            }
+            Action::StartChoiceMode(coll, num, difficulty) => {
+                if let AppScreen::ChoiceScreen(cs) = self.screens.get_mut("choice_screen").unwrap()
+                {
+                    cs.update(choice::Message::Start(coll, num, difficulty));
+                };
+                self.active_screen = "choice_screen".into()
+            }
+            Action::StartFlashcards(coll, scheduler, direction) => {
+                if let AppScreen::FlashcardScreen(fs) =
+                    self.screens.get_mut("flashcard_screen").unwrap()
+                {
+                    fs.update(flashcard::Message::Start(coll, scheduler, direction));
+                };
+                self.active_screen = "flashcard_screen".into()
+            }
+            Action::StartMatching(coll, num) => {
+                if let AppScreen::MatchingScreen(ms) =
+                    self.screens.get_mut("matching_screen").unwrap()
+                {
+                    ms.update(matching::Message::Start(coll, num));
+                };
+                self.active_screen = "matching_screen".into()
+            }
+            Action::StartSprint(coll, require_all) => {
+                if let AppScreen::SprintScreen(ss) = self.screens.get_mut("sprint_screen").unwrap()
+                {
+                    ss.update(sprint::Message::Start(coll, require_all));
+                };
+                self.active_screen = "sprint_screen".into()
+            }
+            Action::StartSurvival(coll, require_all, profile) => {
+                if let AppScreen::SurvivalScreen(ss) =
+                    self.screens.get_mut("survival_screen").unwrap()
+                {
+                    ss.update(survival::Message::Start(coll, require_all, profile));
+                };
+                self.active_screen = "survival_screen".into()
+            }
         }
     }
     pub fn update(&mut self, message: Message) -> Task<Message> {
@@ -159,7 +292,68 @@ impl App {
                     };
                 };
             }
-            Message::KeyPressed(k) => match self.screens.get_mut(&self.active_screen) {
+            Message::StatsMessage(key, sm) => {
+                if let AppScreen::StatsScreen(ss) = self.screens.get_mut(&key).unwrap() {
+                    match ss.update(sm) {
+                        Left(l) => {
+                            return l.map(|v| Message::StatsMessage("stats_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    };
+                };
+            }
+            Message::ChoiceMessage(key, cm) => {
+                if let AppScreen::ChoiceScreen(cs) = self.screens.get_mut(&key).unwrap() {
+                    match cs.update(cm) {
+                        Left(l) => {
+                            return l.map(|v| Message::ChoiceMessage("choice_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    };
+                };
+            }
+            Message::FlashcardMessage(key, fm) => {
+                if let AppScreen::FlashcardScreen(fs) = self.screens.get_mut(&key).unwrap() {
+                    match fs.update(fm) {
+                        Left(l) => {
+                            return l
+                                .map(|v| Message::FlashcardMessage("flashcard_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    };
+                };
+            }
+            Message::MatchingMessage(key, mm) => {
+                if let AppScreen::MatchingScreen(ms) = self.screens.get_mut(&key).unwrap() {
+                    match ms.update(mm) {
+                        Left(l) => {
+                            return l.map(|v| Message::MatchingMessage("matching_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    };
+                };
+            }
+            Message::SprintMessage(key, sm) => {
+                if let AppScreen::SprintScreen(ss) = self.screens.get_mut(&key).unwrap() {
+                    match ss.update(sm) {
+                        Left(l) => {
+                            return l.map(|v| Message::SprintMessage("sprint_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    };
+                };
+            }
+            Message::SurvivalMessage(key, sm) => {
+                if let AppScreen::SurvivalScreen(ss) = self.screens.get_mut(&key).unwrap() {
+                    match ss.update(sm) {
+                        Left(l) => {
+                            return l.map(|v| Message::SurvivalMessage("survival_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    };
+                };
+            }
+            Message::KeyPressed(Key::Named(k)) => match self.screens.get_mut(&self.active_screen) {
                 Some(AppScreen::LearnScreen(ls)) => {
                     match ls.update(learn::Message::KeyPressed(k)) {
                         Left(l) => {
@@ -177,13 +371,91 @@ impl App {
                         Right(action) => self.perform_action(action),
                     };
                 }
+                Some(AppScreen::StatsScreen(ss)) => {
+                    match ss.update(stats::Message::KeyPressed(k)) {
+                        Left(l) => {
+                            return l.map(|v| Message::StatsMessage("stats_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    }
+                }
+                Some(AppScreen::ChoiceScreen(cs)) => {
+                    match cs.update(choice::Message::KeyPressed(Key::Named(k))) {
+                        Left(l) => {
+                            return l.map(|v| Message::ChoiceMessage("choice_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    }
+                }
+                Some(AppScreen::FlashcardScreen(fs)) => {
+                    match fs.update(flashcard::Message::KeyPressed(Key::Named(k))) {
+                        Left(l) => {
+                            return l
+                                .map(|v| Message::FlashcardMessage("flashcard_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    }
+                }
+                Some(AppScreen::MatchingScreen(ms)) => {
+                    match ms.update(matching::Message::KeyPressed(Key::Named(k))) {
+                        Left(l) => {
+                            return l.map(|v| Message::MatchingMessage("matching_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    }
+                }
+                Some(AppScreen::SprintScreen(ss)) => {
+                    match ss.update(sprint::Message::KeyPressed(k)) {
+                        Left(l) => {
+                            return l.map(|v| Message::SprintMessage("sprint_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    }
+                }
+                Some(AppScreen::SurvivalScreen(ss)) => {
+                    match ss.update(survival::Message::KeyPressed(k)) {
+                        Left(l) => {
+                            return l.map(|v| Message::SurvivalMessage("survival_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    }
+                }
                 Some(AppScreen::None) => (),
                 None => (),
             },
+            // only multiple choice, flashcards and matching pairs listen to other keys
+            Message::KeyPressed(k) => match self.screens.get_mut(&self.active_screen) {
+                Some(AppScreen::ChoiceScreen(cs)) => {
+                    match cs.update(choice::Message::KeyPressed(k)) {
+                        Left(l) => {
+                            return l.map(|v| Message::ChoiceMessage("choice_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    }
+                }
+                Some(AppScreen::FlashcardScreen(fs)) => {
+                    match fs.update(flashcard::Message::KeyPressed(k)) {
+                        Left(l) => {
+                            return l
+                                .map(|v| Message::FlashcardMessage("flashcard_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    }
+                }
+                Some(AppScreen::MatchingScreen(ms)) => {
+                    match ms.update(matching::Message::KeyPressed(k)) {
+                        Left(l) => {
+                            return l.map(|v| Message::MatchingMessage("matching_screen".into(), v))
+                        }
+                        Right(action) => self.perform_action(action),
+                    }
+                }
+                _ => (),
+            },
         };
         Task::none()
     }
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self) -> Element<'_, Message> {
         match &self
             .screens
             .get(&self.active_screen)
@@ -198,15 +470,39 @@ impl App {
             AppScreen::TestingScreen(ts) => ts
                 .view()
                 .map(|v| Message::TestingMessage("testing_screen".into(), v)),
+            AppScreen::StatsScreen(ss) => ss
+                .view()
+                .map(|v| Message::StatsMessage("stats_screen".into(), v)),
+            AppScreen::ChoiceScreen(cs) => cs
+                .view()
+                .map(|v| Message::ChoiceMessage("choice_screen".into(), v)),
+            AppScreen::FlashcardScreen(fs) => fs
+                .view()
+                .map(|v| Message::FlashcardMessage("flashcard_screen".into(), v)),
+            AppScreen::MatchingScreen(ms) => ms
+                .view()
+                .map(|v| Message::MatchingMessage("matching_screen".into(), v)),
+            AppScreen::SprintScreen(ss) => ss
+                .view()
+                .map(|v| Message::SprintMessage("sprint_screen".into(), v)),
+            AppScreen::SurvivalScreen(ss) => ss
+                .view()
+                .map(|v| Message::SurvivalMessage("survival_screen".into(), v)),
             AppScreen::None => container(text("Smth is wrong")).center(Fill).into(),
         }
     }
 
     pub fn subscribe(&self) -> Subscription<Message> {
-        iced::keyboard::on_key_press(|key, mods| {
+        let timer = match self.screens.get(&self.active_screen) {
+            Some(AppScreen::SprintScreen(ss)) => ss
+                .subscription()
+                .map(|v| Message::SprintMessage("sprint_screen".into(), v)),
+            _ => Subscription::none(),
+        };
+        let keys = iced::keyboard::on_key_press(|key, mods| {
             match key {
-                Key::Named(Named::Enter) => Some(Message::KeyPressed(Named::Enter)),
-                Key::Named(Named::Escape) => Some(Message::KeyPressed(Named::Escape)),
+                Key::Named(Named::Enter | Named::Escape | Named::Space | Named::Tab)
+                | Key::Character(_) => Some(Message::KeyPressed(key)),
                 _ => None,
             }
             // if Key::Named(Named::Enter) == key {
@@ -218,7 +514,8 @@ impl App {
             // } else {
             //     None
             // }
-        })
+        });
+        Subscription::batch([keys, timer])
     }
 }
 
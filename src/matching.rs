@@ -0,0 +1,257 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use either::Either::{self, Left, Right};
+use iced::{
+    keyboard::{key::Named, Key},
+    widget::{button, column, container, row, text},
+    Alignment::Center,
+    Element,
+    Length::Fill,
+    Task,
+};
+use rand::seq::SliceRandom;
+
+use crate::{
+    checker::Verdict,
+    collection::Collection,
+    store::{self, Review},
+    Action,
+};
+
+// words are picked with keys 1-9, meanings with a-i
+const MAX_PAIRS: usize = 9;
+const MEANING_KEYS: &str = "abcdefghi";
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Start(Arc<Collection>, usize),
+    SelectedWord(usize),
+    SelectedMeaning(usize),
+    KeyPressed(Key),
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchingWidget {
+    selected_collection: Option<Arc<Collection>>,
+    words: Vec<String>,
+    // the same entries shuffled, shown by their meanings.
+    // A matched meaning is swapped into the row of its word
+    meanings: Vec<String>,
+    matched: Vec<bool>,
+    selected_word: Option<usize>,
+    selected_meaning: Option<usize>,
+    // last wrong pair, highlighted until the next pick
+    wrong: Option<(usize, usize)>,
+    mistakes: usize,
+    // when the game started and when the last pair was matched, in ms
+    session: i64,
+    shown_at: i64,
+    end_time: i64,
+    // last error saving to the store, shown on the screen
+    save_status: Option<String>,
+}
+
+impl MatchingWidget {
+    pub fn new() -> Self {
+        Self {
+            selected_collection: None,
+            words: Vec::new(),
+            meanings: Vec::new(),
+            matched: Vec::new(),
+            selected_word: None,
+            selected_meaning: None,
+            wrong: None,
+            mistakes: 0,
+            session: 0,
+            shown_at: 0,
+            end_time: 0,
+            save_status: None,
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.matched.iter().all(|m| *m)
+    }
+
+    // tries the selected pair once both sides are picked
+    fn try_pair(&mut self) {
+        let (Some(w), Some(m)) = (self.selected_word, self.selected_meaning) else {
+            return;
+        };
+        self.selected_word = None;
+        self.selected_meaning = None;
+        let coll = self.selected_collection.clone().unwrap();
+        let word = &self.words[w];
+        let other = &self.meanings[m];
+        // entries with the same meanings are interchangeable
+        let verdict = if coll.words()[word] == coll.words()[other] {
+            Verdict::Correct
+        } else {
+            Verdict::Confused {
+                other: other.clone(),
+            }
+        };
+        let review = Review::new(
+            coll.key(),
+            word,
+            "matching",
+            &coll.words()[other].join(" / "),
+            &verdict,
+            self.shown_at,
+            self.session,
+        );
+        store::or_report(&mut self.save_status, "save review", |s| {
+            s.record_answer(&review, &verdict)
+        });
+        if verdict.is_correct() {
+            self.matched[w] = true;
+            self.meanings.swap(m, w);
+            self.shown_at = Utc::now().timestamp_millis();
+            if self.finished() {
+                self.end_time = self.shown_at;
+            }
+        } else {
+            self.mistakes += 1;
+            self.wrong = Some((w, m));
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Either<Task<Message>, Action> {
+        match message {
+            Message::Start(c, n) => {
+                let keys = c.words().keys().cloned().collect::<Vec<_>>();
+                self.words = keys
+                    .choose_multiple(&mut rand::thread_rng(), n.min(MAX_PAIRS))
+                    .cloned()
+                    .collect();
+                self.meanings = self.words.clone();
+                self.meanings.shuffle(&mut rand::thread_rng());
+                self.matched = vec![false; self.words.len()];
+                self.selected_word = None;
+                self.selected_meaning = None;
+                self.wrong = None;
+                self.mistakes = 0;
+                self.save_status = None;
+                self.session = Utc::now().timestamp_millis();
+                self.shown_at = self.session;
+                self.selected_collection = Some(c);
+            }
+            Message::SelectedWord(i) => {
+                if !self.matched[i] {
+                    self.wrong = None;
+                    self.selected_word = Some(i);
+                    self.try_pair();
+                }
+            }
+            Message::SelectedMeaning(i) => {
+                // matched meanings sit in the row of their word
+                if !self.matched[i] {
+                    self.wrong = None;
+                    self.selected_meaning = Some(i);
+                    self.try_pair();
+                }
+            }
+            Message::KeyPressed(Key::Named(Named::Escape)) => {
+                return Right(Action::ChangeScreen("setup_screen".into()))
+            }
+            Message::KeyPressed(Key::Named(Named::Enter)) => {
+                if self.finished() {
+                    return Right(Action::ChangeScreen("setup_screen".into()));
+                }
+            }
+            Message::KeyPressed(Key::Character(c)) => {
+                let len = self.words.len();
+                if let Ok(n @ 1..=MAX_PAIRS) = c.parse::<usize>() {
+                    if n <= len {
+                        return self.update(Message::SelectedWord(n - 1));
+                    }
+                }
+                if let Some(i) = MEANING_KEYS.find(c.as_str()).filter(|i| *i < len) {
+                    return self.update(Message::SelectedMeaning(i));
+                }
+            }
+            Message::KeyPressed(_) => {}
+        }
+        Left(Task::none())
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let Some(coll) = &self.selected_collection else {
+            return container(text("Smthing not right")).center(Fill).into();
+        };
+        if self.words.is_empty() {
+            return container(text("Collection is empty")).center(Fill).into();
+        }
+        if self.finished() {
+            return container(
+                column![
+                    text("All pairs matched").size(64),
+                    text("Press ENTER to exit").size(12),
+                    text!(
+                        "Time: {:.1}s",
+                        (self.end_time - self.session) as f32 / 1000.
+                    )
+                    .size(24),
+                    text!("Mistakes: {}", self.mistakes).size(24),
+                ]
+                .spacing(10)
+                .align_x(Center),
+            )
+            .center(Fill)
+            .into();
+        }
+        let style = |selected: bool, matched: bool, wrong: bool| {
+            if matched {
+                button::success
+            } else if wrong {
+                button::danger
+            } else if selected {
+                button::primary
+            } else {
+                button::secondary
+            }
+        };
+        let words = self.words.iter().enumerate().map(|(i, word)| {
+            let wrong = self.wrong.is_some_and(|(w, _)| w == i);
+            button(text!("{}. {word}", i + 1).size(20))
+                .width(Fill)
+                .style(style(self.selected_word == Some(i), self.matched[i], wrong))
+                .on_press_maybe((!self.matched[i]).then_some(Message::SelectedWord(i)))
+                .into()
+        });
+        let meanings = self.meanings.iter().enumerate().map(|(i, entry)| {
+            let wrong = self.wrong.is_some_and(|(_, m)| m == i);
+            let matched = self.matched[i];
+            let key = &MEANING_KEYS[i..i + 1];
+            button(text!("{key}. {}", coll.words()[entry].join(" / ")).size(20))
+                .width(Fill)
+                .style(style(self.selected_meaning == Some(i), matched, wrong))
+                .on_press_maybe((!matched).then_some(Message::SelectedMeaning(i)))
+                .into()
+        });
+        container(
+            column![
+                text!("Mistakes: {}", self.mistakes).size(12),
+                row![
+                    column(words).spacing(5).width(Fill),
+                    column(meanings).spacing(5).width(Fill),
+                ]
+                .spacing(20),
+                text("Pick a word (1-9) and its meaning (a-i)").size(12),
+            ]
+            .push_maybe(self.save_status.as_ref().map(|s| text(s).size(12)))
+            .spacing(20)
+            .align_x(Center)
+            .width(700),
+        )
+        .center(Fill)
+        .into()
+    }
+}
+
+impl Default for MatchingWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
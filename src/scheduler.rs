@@ -0,0 +1,408 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{Debug, Display},
+};
+
+use anyhow::Result;
+use rand::seq::SliceRandom;
+
+use crate::{
+    checker::Verdict,
+    collection::Collection,
+    fsrs::Fsrs,
+    store::{self, Store},
+};
+
+pub const DAY: i64 = 24 * 60 * 60;
+// failed cards come back within the same session
+pub const RELEARN_DELAY: i64 = 10 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Grade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Grade {
+    pub const ALL: [Grade; 4] = [Grade::Again, Grade::Hard, Grade::Good, Grade::Easy];
+
+    // from the outcome saved in the review log
+    pub fn from_outcome(outcome: &str) -> Self {
+        match outcome {
+            "correct" | "variant" | "good" => Grade::Good,
+            "easy" => Grade::Easy,
+            "almost" | "typo" | "partial" | "hard" => Grade::Hard,
+            _ => Grade::Again,
+        }
+    }
+
+    // an answer given after hints is at best Hard
+    pub fn with_hints(self, hints: usize) -> Self {
+        if hints > 0 {
+            self.min(Grade::Hard)
+        } else {
+            self
+        }
+    }
+
+    // outcome saved for a self graded flashcard
+    pub fn outcome(&self) -> &'static str {
+        match self {
+            Grade::Again => "again",
+            Grade::Hard => "hard",
+            Grade::Good => "good",
+            Grade::Easy => "easy",
+        }
+    }
+
+    pub fn score(&self) -> f32 {
+        match self {
+            Grade::Again => 0.,
+            Grade::Hard => 0.5,
+            Grade::Good | Grade::Easy => 1.,
+        }
+    }
+}
+
+impl Display for Grade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Grade::Again => "Again",
+            Grade::Hard => "Hard",
+            Grade::Good => "Good",
+            Grade::Easy => "Easy",
+        })
+    }
+}
+
+impl From<&Verdict> for Grade {
+    fn from(verdict: &Verdict) -> Self {
+        Grade::from_outcome(verdict.kind())
+    }
+}
+
+// picks the order in which learn mode shows the entries of a collection
+pub trait Scheduler: Debug {
+    fn review(&mut self, word: &str, grade: Grade, now: i64);
+    // next entry to show, `last` is only repeated when there is nothing else
+    fn next(&self, words: &[String], last: Option<&str>, now: i64) -> Option<String>;
+    fn save(&mut self) -> Result<()>;
+}
+
+// overdue cards first, then cards never seen (`due` is None), then whatever is due soonest
+pub fn pick_next(
+    words: &[String],
+    last: Option<&str>,
+    now: i64,
+    due: impl Fn(&str) -> Option<i64>,
+) -> Option<String> {
+    let candidates = words
+        .iter()
+        .filter(|w| words.len() < 2 || Some(w.as_str()) != last);
+    let overdue = candidates
+        .clone()
+        .filter_map(|w| due(w).map(|d| (w, d)))
+        .filter(|(_, d)| *d <= now)
+        .min_by_key(|(_, d)| *d);
+    if let Some((w, _)) = overdue {
+        return Some(w.clone());
+    }
+    if let Some(w) = candidates.clone().find(|w| due(w).is_none()) {
+        return Some(w.clone());
+    }
+    candidates.min_by_key(|w| due(w).unwrap_or(0)).cloned()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SchedulerKind {
+    Sequential,
+    Sm2,
+    Fsrs,
+    Weighted,
+    Leitner,
+}
+
+impl SchedulerKind {
+    // orders offered for learn mode, the first is the default.
+    // Leitner boxes have their own work mode
+    pub const ALL: [SchedulerKind; 4] = [
+        SchedulerKind::Weighted,
+        SchedulerKind::Sequential,
+        SchedulerKind::Sm2,
+        SchedulerKind::Fsrs,
+    ];
+
+    // None keeps the plain in-order cycling of learn mode
+    pub fn load(&self, collection: &Collection) -> Option<Box<dyn Scheduler>> {
+        match self {
+            SchedulerKind::Sequential => None,
+            SchedulerKind::Sm2 => Some(Box::new(Sm2::load(collection.key()))),
+            SchedulerKind::Fsrs => Some(Box::new(Fsrs::load(collection.key()))),
+            SchedulerKind::Weighted => Some(Box::new(Weighted::load(collection.key()))),
+            SchedulerKind::Leitner => Some(Box::new(Leitner::load(collection))),
+        }
+    }
+}
+
+impl Display for SchedulerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SchedulerKind::Sequential => "In order",
+            SchedulerKind::Sm2 => "SM-2",
+            SchedulerKind::Fsrs => "FSRS",
+            SchedulerKind::Weighted => "Weighted random",
+            SchedulerKind::Leitner => "Leitner",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sm2Card {
+    ease: f32,
+    // days
+    interval: f32,
+    repetitions: u32,
+    due: i64,
+}
+
+impl Default for Sm2Card {
+    fn default() -> Self {
+        Self {
+            ease: 2.5,
+            interval: 0.,
+            repetitions: 0,
+            due: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Sm2 {
+    collection: String,
+    cards: HashMap<String, Sm2Card>,
+}
+
+impl Sm2 {
+    // state is saved as "ease interval repetitions"
+    pub fn load(collection: &str) -> Self {
+        let mut cards = HashMap::new();
+        let saved = store::or_warn("load schedule", |s| s.cards("sm2", collection));
+        for (word, state, due) in saved.unwrap_or_default() {
+            let cols = state.split(' ').collect::<Vec<_>>();
+            if cols.len() < 3 {
+                continue;
+            }
+            let card = Sm2Card {
+                ease: cols[0].parse().unwrap_or(2.5),
+                interval: cols[1].parse().unwrap_or(0.),
+                repetitions: cols[2].parse().unwrap_or(0),
+                due,
+            };
+            cards.insert(word, card);
+        }
+        Self {
+            collection: collection.to_owned(),
+            cards,
+        }
+    }
+}
+
+impl Scheduler for Sm2 {
+    fn review(&mut self, word: &str, grade: Grade, now: i64) {
+        let card = self.cards.entry(word.to_owned()).or_default();
+        let quality: f32 = match grade {
+            Grade::Again => 1.,
+            Grade::Hard => 3.,
+            Grade::Good => 4.,
+            Grade::Easy => 5.,
+        };
+        if grade == Grade::Again {
+            card.repetitions = 0;
+            card.interval = 0.;
+            card.due = now + RELEARN_DELAY;
+        } else {
+            card.interval = match card.repetitions {
+                0 => 1.,
+                1 => 6.,
+                _ => card.interval * card.ease,
+            };
+            card.repetitions += 1;
+            card.due = now + (card.interval * DAY as f32) as i64;
+        }
+        card.ease = (card.ease + 0.1 - (5. - quality) * (0.08 + (5. - quality) * 0.02)).max(1.3);
+    }
+
+    fn next(&self, words: &[String], last: Option<&str>, now: i64) -> Option<String> {
+        pick_next(words, last, now, |w| self.cards.get(w).map(|c| c.due))
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let cards = self.cards.iter().map(|(word, c)| {
+            let state = format!("{} {} {}", c.ease, c.interval, c.repetitions);
+            (word.as_str(), state, c.due)
+        });
+        store::with_default(|s| s.save_cards("sm2", &self.collection, cards))
+    }
+}
+
+// review cadence in days of every box, collections can set their own:
+// $ leitner = 1, 2, 4, 8, 16
+pub const DEFAULT_LEITNER_CADENCE: [u32; 5] = [1, 2, 4, 8, 16];
+
+#[derive(Debug)]
+pub struct Leitner {
+    collection: String,
+    cadence: Vec<u32>,
+    // word -> (box, due)
+    cards: HashMap<String, (usize, i64)>,
+}
+
+impl Leitner {
+    // state is saved as the box number
+    pub fn load(coll: &Collection) -> Self {
+        let cadence = coll
+            .variable("leitner")
+            .map(|v| {
+                v.split(',')
+                    .filter_map(|d| d.trim().parse::<u32>().ok())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|v| !v.is_empty())
+            .unwrap_or(DEFAULT_LEITNER_CADENCE.to_vec());
+        let mut cards = HashMap::new();
+        let saved = store::or_warn("load schedule", |s| s.cards("leitner", coll.key()));
+        for (word, state, due) in saved.unwrap_or_default() {
+            let b = state.parse::<usize>().unwrap_or(0).min(cadence.len() - 1);
+            cards.insert(word, (b, due));
+        }
+        Self {
+            collection: coll.key().to_owned(),
+            cadence,
+            cards,
+        }
+    }
+
+    // number of `words` in every box, words never reviewed sit in the first one
+    pub fn box_counts(&self, words: &[String]) -> Vec<usize> {
+        let mut counts = vec![0; self.cadence.len()];
+        for w in words {
+            counts[self.cards.get(w).map_or(0, |(b, _)| *b)] += 1;
+        }
+        counts
+    }
+}
+
+impl Scheduler for Leitner {
+    // correct answers move the card one box up, wrong ones back to the first box
+    fn review(&mut self, word: &str, grade: Grade, now: i64) {
+        let last = self.cadence.len() - 1;
+        let card = self.cards.entry(word.to_owned()).or_insert((0, now));
+        card.0 = match grade {
+            Grade::Again => 0,
+            Grade::Hard => card.0,
+            Grade::Good | Grade::Easy => (card.0 + 1).min(last),
+        };
+        // missed cards are repeated within the session
+        card.1 = if grade == Grade::Again {
+            now + RELEARN_DELAY
+        } else {
+            now + self.cadence[card.0] as i64 * DAY
+        };
+    }
+
+    // lowest box first, so struggling cards come back before well known ones
+    fn next(&self, words: &[String], last: Option<&str>, now: i64) -> Option<String> {
+        let candidates = words
+            .iter()
+            .filter(|w| words.len() < 2 || Some(w.as_str()) != last);
+        let due = candidates
+            .clone()
+            .filter_map(|w| self.cards.get(w).map(|(b, d)| (w, *b, *d)))
+            .filter(|(_, _, d)| *d <= now)
+            .min_by_key(|(_, b, d)| (*b, *d));
+        if let Some((w, _, _)) = due {
+            return Some(w.clone());
+        }
+        pick_next(words, last, now, |w| self.cards.get(w).map(|(_, d)| *d))
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let cards = self
+            .cards
+            .iter()
+            .map(|(word, (b, due))| (word.as_str(), b.to_string(), *due));
+        store::with_default(|s| s.save_cards("leitner", &self.collection, cards))
+    }
+}
+
+// answers that make up the recent error rate of an entry
+const RECENT_ANSWERS: usize = 10;
+
+#[derive(Debug, Default)]
+struct History {
+    // scores of the last answers, oldest first
+    scores: VecDeque<f32>,
+    last_seen: i64,
+}
+
+// random order where entries answered badly lately, or not seen for a while, come up more.
+// Everything is rebuilt from the review log, so there is nothing to save
+#[derive(Debug)]
+pub struct Weighted {
+    entries: HashMap<String, History>,
+}
+
+impl Weighted {
+    pub fn load(collection: &str) -> Self {
+        let mut weighted = Self {
+            entries: HashMap::new(),
+        };
+        let reviews = store::or_warn("load review log", |s| s.reviews(Some(collection)));
+        for r in reviews.unwrap_or_default() {
+            let grade = Grade::from_outcome(&r.outcome).with_hints(r.hints);
+            weighted.review(&r.entry, grade, r.timestamp);
+        }
+        weighted
+    }
+
+    // entries never answered count as half wrong and a day old
+    fn weight(&self, word: &str, now: i64) -> f64 {
+        let (error, elapsed) = match self.entries.get(word) {
+            Some(h) if !h.scores.is_empty() => {
+                let mean = h.scores.iter().sum::<f32>() / h.scores.len() as f32;
+                (1. - mean as f64, (now - h.last_seen).max(0))
+            }
+            _ => (0.5, DAY),
+        };
+        // the error rate leads, time since last seen grows slowly
+        (0.1 + error) * (1. + (1. + elapsed as f64 / 3600.).ln())
+    }
+}
+
+impl Scheduler for Weighted {
+    fn review(&mut self, word: &str, grade: Grade, now: i64) {
+        let history = self.entries.entry(word.to_owned()).or_default();
+        history.scores.push_back(grade.score());
+        if history.scores.len() > RECENT_ANSWERS {
+            history.scores.pop_front();
+        }
+        history.last_seen = now;
+    }
+
+    fn next(&self, words: &[String], last: Option<&str>, now: i64) -> Option<String> {
+        let candidates = words
+            .iter()
+            .filter(|w| words.len() < 2 || Some(w.as_str()) != last)
+            .collect::<Vec<_>>();
+        candidates
+            .choose_weighted(&mut rand::thread_rng(), |w| self.weight(w, now))
+            .ok()
+            .map(|w| (*w).clone())
+    }
+
+    fn save(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -1,26 +1,50 @@
 use std::sync::Arc;
 
 use either::Either::{self, Left, Right};
-use iced::{widget::{button, column, container, pick_list, radio, row, slider, text, tooltip}, Alignment::Center, Element, Length::Fill, Task};
+use iced::{widget::{button, checkbox, column, container, pick_list, radio, row, slider, text, text_input, tooltip}, Alignment::Center, Element, Length::Fill, Task};
 use rfd::FileDialog;
 use walkdir::WalkDir;
 
-use crate::{collection::Collection, Action};
+use crate::{choice, collection::{Collection, Direction}, fsrs::Fsrs, learn::LearnOptions, scheduler::{Leitner, SchedulerKind}, store::{self, DailyGoals, DayProgress, MistakeWindow}, Action};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum WorkMode {
     LearnMode,
     TestMode,
+    Leitner,
+    Mistakes,
+    Choice,
+    Flashcards,
+    Matching,
+    Sprint,
+    Survival,
 }
 
+const MISTAKES_UNITS: [&str; 2] = ["sessions", "days"];
+
 #[derive(Debug, Clone)]
 pub enum Message {
     SelectedCollection(Arc<Collection>),
     SelectedMode(WorkMode),
     SelectedNumberOfWords(u8),
+    SelectedDifficulty(u8),
+    SelectedNewPerDay(u16),
+    SelectedReviewsPerDay(u16),
+    SelectedMinutesPerDay(u16),
+    EndAtGoalToggled(bool),
+    RequireAllToggled(bool),
+    SelectedScheduler(SchedulerKind),
+    SelectedDirection(Direction),
+    ProfileChanged(String),
+    SelectedMistakesSpan(u8),
+    SelectedMistakesUnit(&'static str),
+    FitFsrsButton,
+    FsrsFitted(Result<(), String>),
+    Refresh,
     CollectionsLoaded(Option<Vec<Arc<Collection>>>),
     CollectionDirectoryButton,
     StartButtonClicked,
+    StatsButtonClicked,
 }
 
 #[derive(Debug, Clone)]
@@ -28,29 +52,187 @@ pub struct SetupWidget {
     selected_collection: Option<Arc<Collection>>,
     selected_work_mode: WorkMode,
     selected_number_of_test_words: u8,
+    // how similar multiple choice distractors are to the answer
+    selected_difficulty: u8,
+    // Learn Mode and Leitner introduce at most `goals.new_words` per day
+    goals: DailyGoals,
+    end_at_goal: bool,
+    today: DayProgress,
+    require_all_meanings: bool,
+    selected_scheduler: SchedulerKind,
+    selected_direction: Direction,
+    // name shown on the survival leaderboard
+    profile: String,
+    fsrs_status: Option<String>,
+    // cards in every Leitner box of the selected collection
+    leitner_counts: Vec<usize>,
+    // "Review mistakes" looks at the last `mistakes_span` sessions or days
+    mistakes_span: u8,
+    mistakes_unit: &'static str,
+    mistakes_status: Option<String>,
+    // last error saving a setting
+    save_status: Option<String>,
     collections: Vec<Arc<Collection>>,
 }
 
 impl SetupWidget {
     pub fn new() -> Self {
+        let setting = |key: &str| store::or_warn("load settings", |s| s.setting(key)).flatten();
         Self {
             selected_collection: None,
             selected_work_mode: WorkMode::LearnMode,
             collections: Vec::new(),
-            selected_number_of_test_words: 5,
+            selected_number_of_test_words: setting("test_words")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+            selected_difficulty: setting("choice_difficulty")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(choice::MAX_DIFFICULTY / 2),
+            goals: DailyGoals {
+                new_words: setting("new_per_day")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(10),
+                reviews: setting("reviews_per_day")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(50),
+                minutes: setting("minutes_per_day")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(10),
+            },
+            end_at_goal: setting("end_at_goal").is_some_and(|v| v == "true"),
+            today: store::or_warn("load progress", |s| s.today()).unwrap_or_default(),
+            require_all_meanings: setting("require_all").is_some_and(|v| v == "true"),
+            selected_scheduler: setting("scheduler")
+                .and_then(|v| SchedulerKind::ALL.into_iter().find(|k| k.to_string() == v))
+                .unwrap_or(SchedulerKind::ALL[0]),
+            selected_direction: setting("direction")
+                .and_then(|v| Direction::ALL.into_iter().find(|d| d.to_string() == v))
+                .unwrap_or(Direction::Forward),
+            profile: setting("profile").unwrap_or("Player".into()),
+            fsrs_status: None,
+            leitner_counts: Vec::new(),
+            mistakes_span: setting("mistakes_span")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
+            mistakes_unit: setting("mistakes_unit")
+                .and_then(|v| MISTAKES_UNITS.into_iter().find(|u| *u == v))
+                .unwrap_or(MISTAKES_UNITS[0]),
+            mistakes_status: None,
+            save_status: None,
         }
     }
+    fn refresh_leitner_counts(&mut self) {
+        self.leitner_counts = match &self.selected_collection {
+            Some(c) if self.selected_work_mode == WorkMode::Leitner => {
+                let words = c.words().keys().cloned().collect::<Vec<_>>();
+                Leitner::load(c).box_counts(&words)
+            }
+            _ => Vec::new(),
+        };
+    }
+
+    fn learn_options(&self, scheduler: SchedulerKind) -> LearnOptions {
+        LearnOptions {
+            require_all: self.require_all_meanings,
+            scheduler,
+            direction: self.selected_direction,
+            goals: self.goals,
+            end_at_goal: self.end_at_goal,
+        }
+    }
+
+    fn mistakes_window(&self) -> MistakeWindow {
+        match self.mistakes_unit {
+            "days" => MistakeWindow::Days(self.mistakes_span.into()),
+            _ => MistakeWindow::Sessions(self.mistakes_span.into()),
+        }
+    }
+
     pub fn update(&mut self, message: Message) -> Either<Task<Message>, Action> {
         match message {
-            Message::SelectedCollection(v) => self.selected_collection = Some(v),
-            Message::SelectedMode(mode) => self.selected_work_mode = mode,
-            Message::SelectedNumberOfWords(num) => self.selected_number_of_test_words = num,
+            Message::SelectedCollection(v) => {
+                self.selected_collection = Some(v);
+                self.refresh_leitner_counts();
+            }
+            Message::SelectedMode(mode) => {
+                self.selected_work_mode = mode;
+                self.mistakes_status = None;
+                self.refresh_leitner_counts();
+            }
+            Message::Refresh => {
+                self.refresh_leitner_counts();
+                self.today = store::or_warn("load progress", |s| s.today()).unwrap_or_default();
+            }
+            Message::SelectedNumberOfWords(num) => {
+                self.selected_number_of_test_words = num;
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("test_words", &num.to_string()));
+            }
+            Message::SelectedDifficulty(d) => {
+                self.selected_difficulty = d;
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("choice_difficulty", &d.to_string()));
+            }
+            Message::SelectedNewPerDay(n) => {
+                self.goals.new_words = n;
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("new_per_day", &n.to_string()));
+            }
+            Message::SelectedReviewsPerDay(n) => {
+                self.goals.reviews = n;
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("reviews_per_day", &n.to_string()));
+            }
+            Message::SelectedMinutesPerDay(n) => {
+                self.goals.minutes = n;
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("minutes_per_day", &n.to_string()));
+            }
+            Message::EndAtGoalToggled(v) => {
+                self.end_at_goal = v;
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("end_at_goal", &v.to_string()));
+            }
+            Message::RequireAllToggled(v) => {
+                self.require_all_meanings = v;
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("require_all", &v.to_string()));
+            }
+            Message::SelectedScheduler(kind) => {
+                self.selected_scheduler = kind;
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("scheduler", &kind.to_string()));
+            }
+            Message::SelectedDirection(direction) => {
+                self.selected_direction = direction;
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("direction", &direction.to_string()));
+            }
+            Message::ProfileChanged(profile) => {
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("profile", &profile));
+                self.profile = profile;
+            }
+            Message::SelectedMistakesSpan(n) => {
+                self.mistakes_span = n;
+                self.mistakes_status = None;
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("mistakes_span", &n.to_string()));
+            }
+            Message::SelectedMistakesUnit(unit) => {
+                self.mistakes_unit = unit;
+                self.mistakes_status = None;
+                store::or_report(&mut self.save_status, "save settings", |s| s.set_setting("mistakes_unit", unit));
+            }
+            Message::FitFsrsButton => {
+                self.fsrs_status = Some("Optimising...".into());
+                return Left(Task::perform(
+                    async { Fsrs::fit().map(|_| ()).map_err(|e| e.to_string()) },
+                    Message::FsrsFitted,
+                ));
+            }
+            Message::FsrsFitted(r) => {
+                self.fsrs_status = Some(match r {
+                    Ok(()) => "FSRS parameters updated".into(),
+                    Err(e) => format!("Could not optimise: {e}"),
+                })
+            }
             Message::CollectionsLoaded(colls) => {
                 if let Some(colls) = colls {
                     if (!colls.is_empty()) {
                         self.collections = colls.clone();
                         if self.selected_collection.is_none() {
                             self.selected_collection = colls.first().cloned();
+                            self.refresh_leitner_counts();
                         }
                         // return Right(Action::SendColections(colls.clone()))
                     }
@@ -85,11 +267,37 @@ impl SetupWidget {
                 ))
                 
             }
+            Message::StatsButtonClicked => {
+                if let Some(c) = &self.selected_collection {
+                    return Right(Action::ShowStats(c.clone()));
+                }
+            }
             Message::StartButtonClicked => {
+                if let (Some(c), WorkMode::Mistakes) = (&self.selected_collection, self.selected_work_mode) {
+                    let window = self.mistakes_window();
+                    let mut words = store::or_warn("load review log", |s| s.mistakes(c.key(), window)).unwrap_or_default();
+                    // the collection file may have changed since the mistakes were logged
+                    words.retain(|w| c.words().contains_key(w));
+                    if words.is_empty() {
+                        self.mistakes_status = Some("No mistakes to review".into());
+                        return Left(Task::none());
+                    }
+                    return Right(Action::StartReview(c.clone(), words, self.require_all_meanings, self.selected_direction));
+                }
                 if self.selected_collection.is_some() {
                     return Right(match self.selected_work_mode{
-                        WorkMode::LearnMode => Action::StartLearnMode(self.selected_collection.clone().unwrap()),
-                        WorkMode::TestMode => Action::StartTestMode(self.selected_collection.clone().unwrap(), self.selected_number_of_test_words.into()),
+                        WorkMode::LearnMode => Action::StartLearnMode(self.selected_collection.clone().unwrap(), self.learn_options(self.selected_scheduler)),
+                        WorkMode::TestMode => Action::StartTestMode(self.selected_collection.clone().unwrap(), self.selected_number_of_test_words.into(), self.require_all_meanings, self.selected_direction),
+                        WorkMode::Leitner => Action::StartLearnMode(self.selected_collection.clone().unwrap(), self.learn_options(SchedulerKind::Leitner)),
+                        WorkMode::Choice => Action::StartChoiceMode(self.selected_collection.clone().unwrap(), self.selected_number_of_test_words.into(), self.selected_difficulty),
+                        WorkMode::Flashcards => Action::StartFlashcards(self.selected_collection.clone().unwrap(), self.selected_scheduler, self.selected_direction),
+                        WorkMode::Matching => Action::StartMatching(self.selected_collection.clone().unwrap(), self.selected_number_of_test_words.into()),
+                        WorkMode::Sprint => Action::StartSprint(self.selected_collection.clone().unwrap(), self.require_all_meanings),
+                        WorkMode::Survival => {
+                            let profile = self.profile.trim();
+                            Action::StartSurvival(self.selected_collection.clone().unwrap(), self.require_all_meanings, if profile.is_empty() { "Player".into() } else { profile.to_owned() })
+                        }
+                        WorkMode::Mistakes => unreachable!(),
                     });
                     // return Right(Action::ChangeScreen("learn_screen".into()))
                 }
@@ -98,7 +306,7 @@ impl SetupWidget {
         Left(Task::none())
     }
 
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self) -> Element<'_, Message> {
         container(container(column![
             column![
             row![button("Load").on_press(Message::CollectionDirectoryButton),
@@ -115,7 +323,7 @@ impl SetupWidget {
                     Some(self.selected_work_mode),
                     Message::SelectedMode
                 ),
-                "In this mode you will indefinitly shown questions,\n there is no timer, and to stop just quit",
+                "In this mode you will be shown questions until the daily goal\n if you choose so, or until ESC",
                 tooltip::Position::Top
             ).gap(10).style(container::rounded_box),
             radio(
@@ -124,16 +332,86 @@ impl SetupWidget {
                 Some(self.selected_work_mode),
                 Message::SelectedMode
             ),
+            tooltip(
+                radio(
+                    "Multiple choice",
+                    WorkMode::Choice,
+                    Some(self.selected_work_mode),
+                    Message::SelectedMode
+                ),
+                "Pick the right meaning out of 4, with keys 1-4",
+                tooltip::Position::Top
+            ).gap(10).style(container::rounded_box),
+            tooltip(
+                radio(
+                    "Flashcards",
+                    WorkMode::Flashcards,
+                    Some(self.selected_work_mode),
+                    Message::SelectedMode
+                ),
+                "Flip the card with SPACE and grade yourself with keys 1-4",
+                tooltip::Position::Top
+            ).gap(10).style(container::rounded_box),
+            tooltip(
+                radio(
+                    "Matching pairs",
+                    WorkMode::Matching,
+                    Some(self.selected_work_mode),
+                    Message::SelectedMode
+                ),
+                "Pair up to 9 phrases with their meanings, as fast as you can",
+                tooltip::Position::Top
+            ).gap(10).style(container::rounded_box),
+            tooltip(
+                radio(
+                    "Sprint",
+                    WorkMode::Sprint,
+                    Some(self.selected_work_mode),
+                    Message::SelectedMode
+                ),
+                "Answer as many phrases as you can in 60 seconds",
+                tooltip::Position::Top
+            ).gap(10).style(container::rounded_box),
+            tooltip(
+                radio(
+                    "Survival",
+                    WorkMode::Survival,
+                    Some(self.selected_work_mode),
+                    Message::SelectedMode
+                ),
+                "Keep answering until your third mistake",
+                tooltip::Position::Top
+            ).gap(10).style(container::rounded_box),
+            tooltip(
+                radio(
+                    "Leitner",
+                    WorkMode::Leitner,
+                    Some(self.selected_work_mode),
+                    Message::SelectedMode
+                ),
+                "Cards move up a box when answered correctly and back to the first box when not,\n higher boxes are reviewed less often",
+                tooltip::Position::Top
+            ).gap(10).style(container::rounded_box),
+            tooltip(
+                radio(
+                    "Review mistakes",
+                    WorkMode::Mistakes,
+                    Some(self.selected_work_mode),
+                    Message::SelectedMode
+                ),
+                "Test yourself on the phrases you answered wrong recently",
+                tooltip::Position::Top
+            ).gap(10).style(container::rounded_box),
 
             ]
-                .spacing(10),
+                .spacing(10).wrap(),
                 row![
                     tooltip(
                         text!(
                             "Phrase count ({:02})",
                             self.selected_number_of_test_words
                         ),
-                        "In Test Mode",
+                        "In Test Mode, Multiple choice and Matching pairs",
                         tooltip::Position::Top
                     ).gap(10).style(container::rounded_box), 
                     slider(
@@ -142,6 +420,88 @@ impl SetupWidget {
                         Message::SelectedNumberOfWords
                     )
                 ].spacing(10),
+                row![
+                    tooltip(
+                        text("Order"),
+                        "In Learn Mode and Flashcards, SM-2 and FSRS show the phrases that are due for review first,\n weighted random brings back the ones you got wrong lately more often",
+                        tooltip::Position::Top
+                    ).gap(10).style(container::rounded_box),
+                    pick_list(
+                        SchedulerKind::ALL,
+                        Some(self.selected_scheduler),
+                        Message::SelectedScheduler
+                    )
+                ].push_maybe((self.selected_scheduler == SchedulerKind::Fsrs).then(|| tooltip(
+                    button("Optimise").on_press(Message::FitFsrsButton),
+                    "Fit FSRS parameters to your own review history",
+                    tooltip::Position::Top
+                ).gap(10).style(container::rounded_box)))
+                .push_maybe(self.fsrs_status.as_ref().map(|s| text(s).size(12)))
+                .spacing(10),
+                row![].push_maybe((self.selected_work_mode == WorkMode::Mistakes).then(|| row![
+                    text!("Last {:02}", self.mistakes_span),
+                    slider(1..=30, self.mistakes_span, Message::SelectedMistakesSpan),
+                    pick_list(MISTAKES_UNITS, Some(self.mistakes_unit), Message::SelectedMistakesUnit),
+                ].spacing(10).align_y(Center)))
+                .push_maybe(self.mistakes_status.as_ref().map(|s| text(s).size(12))),
+                column![].push_maybe(matches!(self.selected_work_mode, WorkMode::LearnMode | WorkMode::Leitner).then(|| column![
+                    row![
+                        tooltip(
+                            text!("New words per day ({:02})", self.goals.new_words),
+                            "Words never seen before are introduced with their details until the daily limit is reached",
+                            tooltip::Position::Top
+                        ).gap(10).style(container::rounded_box),
+                        slider(1..=50, self.goals.new_words, Message::SelectedNewPerDay),
+                    ].spacing(10),
+                    row![
+                        text!("Reviews per day ({:03})", self.goals.reviews),
+                        slider(10..=500, self.goals.reviews, Message::SelectedReviewsPerDay).step(10u16),
+                    ].spacing(10),
+                    row![
+                        text!("Minutes per day ({:03})", self.goals.minutes),
+                        slider(5..=120, self.goals.minutes, Message::SelectedMinutesPerDay).step(5u16),
+                    ].spacing(10),
+                    tooltip(
+                        checkbox("End the session at the daily goal", self.end_at_goal)
+                            .on_toggle(Message::EndAtGoalToggled),
+                        "Otherwise the session goes on until ESC, which shows the summary",
+                        tooltip::Position::Top
+                    ).gap(10).style(container::rounded_box),
+                ].spacing(10))),
+                text!("Today: {}", self.today.describe(&self.goals)).size(12),
+                row![].push_maybe((self.selected_work_mode == WorkMode::Survival).then(|| row![
+                    text("Profile"),
+                    text_input("Player", &self.profile).on_input(Message::ProfileChanged),
+                ].spacing(10).align_y(Center))),
+                row![].push_maybe((self.selected_work_mode == WorkMode::Choice).then(|| row![
+                    tooltip(
+                        text!("Difficulty ({:02})", self.selected_difficulty),
+                        "Higher picks wrong options that look like the answer,\n share its tags or were confused with it before",
+                        tooltip::Position::Top
+                    ).gap(10).style(container::rounded_box),
+                    slider(0..=choice::MAX_DIFFICULTY, self.selected_difficulty, Message::SelectedDifficulty),
+                ].spacing(10))),
+                row![
+                    tooltip(
+                        text("Direction"),
+                        "In Learn, Test, Leitner and Flashcards, meaning → word accepts any word with the shown meaning",
+                        tooltip::Position::Top
+                    ).gap(10).style(container::rounded_box),
+                    pick_list(
+                        Direction::ALL,
+                        Some(self.selected_direction),
+                        Message::SelectedDirection
+                    )
+                ].spacing(10),
+                row(self.leitner_counts.iter().enumerate().map(|(i, n)| {
+                    column![text!("Box {}", i + 1).size(12), text(n).size(20)].align_x(Center).into()
+                })).spacing(15),
+                tooltip(
+                    checkbox("Require all meanings", self.require_all_meanings)
+                        .on_toggle(Message::RequireAllToggled),
+                    "Type every meaning of the phrase, separated by ','",
+                    tooltip::Position::Top
+                ).gap(10).style(container::rounded_box),
 
                 ]
                     .spacing(10)
@@ -151,7 +511,9 @@ impl SetupWidget {
                     .padding(10),
                     row![
                         button("Start").on_press(Message::StartButtonClicked),
-                    ].padding(5).spacing(5)
+                        button("Stats").on_press(Message::StatsButtonClicked),
+                    ].padding(5).spacing(5),
+                    column![].push_maybe(self.save_status.as_ref().map(|s| text(s).size(12))).padding(5),
                     ]).width(400).height(500).style(container::bordered_box)
                     )
                     .center(Fill).into()
@@ -0,0 +1,260 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    checker::{AnswerChecker, Verdict},
+    collection::Collection,
+    store::{self, Review},
+    Action,
+};
+use chrono::Utc;
+use either::Either::{self, Left, Right};
+use iced::{
+    keyboard::key::Named,
+    widget::{column, container, text, text_input, Text},
+    Alignment::Center,
+    Color, Element,
+    Length::Fill,
+    Subscription, Task,
+};
+
+const SPRINT_MS: i64 = 60_000;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Start(Arc<Collection>, bool),
+    InputChanged(String),
+    InputSubmit,
+    Tick,
+    KeyPressed(Named),
+}
+
+#[derive(Debug, Clone)]
+pub struct SprintWidget {
+    selected_collection: Option<Arc<Collection>>,
+    checker: AnswerChecker,
+    word: String,
+    inputed: String,
+    // (word, verdict) of the last answer
+    last: Option<(String, Verdict)>,
+    answered: usize,
+    score: f32,
+    // best score of the collection before this sprint
+    best: Option<f32>,
+    start_message: bool,
+    end_message: bool,
+    // in ms
+    started_at: i64,
+    remaining: i64,
+    shown_at: i64,
+    // last error saving to the store, shown on the screen
+    save_status: Option<String>,
+}
+
+impl SprintWidget {
+    pub fn new() -> Self {
+        Self {
+            selected_collection: None,
+            checker: AnswerChecker::new(),
+            word: String::new(),
+            inputed: String::new(),
+            last: None,
+            answered: 0,
+            score: 0.,
+            best: None,
+            start_message: true,
+            end_message: false,
+            started_at: 0,
+            remaining: SPRINT_MS,
+            shown_at: 0,
+            save_status: None,
+        }
+    }
+
+    fn best_key(coll: &Collection) -> String {
+        format!("sprint_best:{}", coll.key())
+    }
+
+    fn next_word(&mut self) {
+        let coll = self.selected_collection.as_ref().unwrap();
+        self.word = coll.random_word(&self.word);
+        self.shown_at = Utc::now().timestamp_millis();
+    }
+
+    fn finish(&mut self) {
+        self.end_message = true;
+        self.remaining = 0;
+        let coll = self.selected_collection.clone().unwrap();
+        if self.best.is_none_or(|best| self.score > best) {
+            let key = Self::best_key(&coll);
+            store::or_report(&mut self.save_status, "save personal best", |s| {
+                s.set_setting(&key, &self.score.to_string())
+            });
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Either<Task<Message>, Action> {
+        match message {
+            Message::Start(c, require_all) => {
+                self.checker = AnswerChecker::for_session(&c, require_all);
+                let key = Self::best_key(&c);
+                self.best = store::or_warn("load personal best", |s| s.setting(&key))
+                    .flatten()
+                    .and_then(|v| v.parse().ok());
+                self.selected_collection = Some(c);
+                self.inputed.clear();
+                self.last = None;
+                self.answered = 0;
+                self.score = 0.;
+                self.save_status = None;
+                self.start_message = true;
+                self.end_message = false;
+                self.remaining = SPRINT_MS;
+            }
+            Message::InputChanged(input) => self.inputed = input,
+            Message::InputSubmit => {
+                if self.end_message || self.inputed.is_empty() {
+                    return Left(Task::none());
+                }
+                let coll = self.selected_collection.clone().unwrap();
+                let verdict = self.checker.check_entry(&coll, &self.word, &self.inputed);
+                let review = Review::new(
+                    coll.key(),
+                    &self.word,
+                    "sprint",
+                    &self.inputed,
+                    &verdict,
+                    self.shown_at,
+                    self.started_at,
+                );
+                store::or_report(&mut self.save_status, "save review", |s| {
+                    s.record_answer(&review, &verdict)
+                });
+                self.answered += 1;
+                self.score += verdict.score();
+                self.last = Some((self.word.clone(), verdict));
+                self.inputed.clear();
+                self.next_word();
+            }
+            Message::Tick => {
+                if !self.start_message && !self.end_message {
+                    self.remaining = SPRINT_MS - (Utc::now().timestamp_millis() - self.started_at);
+                    if self.remaining <= 0 {
+                        self.finish();
+                    }
+                }
+            }
+            Message::KeyPressed(Named::Escape) => {
+                return Right(Action::ChangeScreen("setup_screen".into()))
+            }
+            Message::KeyPressed(Named::Enter) => {
+                if self.end_message {
+                    return Right(Action::ChangeScreen("setup_screen".into()));
+                }
+                if self.start_message {
+                    self.start_message = false;
+                    self.started_at = Utc::now().timestamp_millis();
+                    self.next_word();
+                    return Left(text_input::focus("sprint_input_id"));
+                }
+            }
+            Message::KeyPressed(_) => {}
+        }
+        Left(Task::none())
+    }
+
+    // the countdown only ticks while a sprint is running
+    pub fn subscription(&self) -> Subscription<Message> {
+        if self.start_message || self.end_message {
+            return Subscription::none();
+        }
+        iced::time::every(Duration::from_millis(100)).map(|_| Message::Tick)
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let Some(coll) = &self.selected_collection else {
+            return container(text("Smthing not right")).center(Fill).into();
+        };
+        if coll.words().is_empty() {
+            return container(text("Collection is empty")).center(Fill).into();
+        }
+        let best = self.best.map_or("-".into(), |b| b.to_string());
+        if self.start_message {
+            return container(
+                column![
+                    text("60 second sprint").size(64),
+                    text("Press ENTER to start").size(12),
+                    text!("Personal best: {best}").size(20),
+                ]
+                .spacing(10)
+                .align_x(Center),
+            )
+            .center(Fill)
+            .into();
+        }
+        if self.end_message {
+            let record = self.best.is_none_or(|b| self.score > b);
+            return container(
+                column![
+                    text("Time's up").size(64),
+                    text("Press ENTER to exit").size(12),
+                    text!("Score: {} / {}", self.score, self.answered).size(24),
+                    text(if record {
+                        "New personal best!".to_owned()
+                    } else {
+                        format!("Personal best: {best}")
+                    })
+                    .size(20),
+                ]
+                .push_maybe(self.save_status.as_ref().map(|s| text(s).size(12)))
+                .spacing(10)
+                .align_x(Center),
+            )
+            .center(Fill)
+            .into();
+        }
+        let last = self
+            .last
+            .as_ref()
+            .map(|(word, verdict)| last_answer(coll, word, verdict));
+        container(
+            column![
+                text!("{:.1}s", self.remaining.max(0) as f32 / 1000.).size(32),
+                text!("Score: {}", self.score).size(16),
+                text(&self.word).size(48),
+                text_input("Type...", &self.inputed)
+                    .id("sprint_input_id")
+                    .on_input(Message::InputChanged)
+                    .on_submit(Message::InputSubmit),
+            ]
+            .push_maybe(last)
+            .push_maybe(self.save_status.as_ref().map(|s| text(s).size(12)))
+            .spacing(20)
+            .align_x(Center)
+            .width(400),
+        )
+        .center(Fill)
+        .into()
+    }
+}
+
+// the previous word with its meanings, green when it was right
+pub fn last_answer<'a>(coll: &Collection, word: &str, verdict: &Verdict) -> Text<'a> {
+    let color = if verdict.is_correct() {
+        Color::new(0., 0.6, 0., 1.)
+    } else {
+        Color::new(0.8, 0., 0., 1.)
+    };
+    text!(
+        "{word}: {} ({})",
+        coll.words()[word].join(" / "),
+        verdict.label()
+    )
+    .size(16)
+    .color(color)
+}
+
+impl Default for SprintWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
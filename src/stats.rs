@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use either::Either::{self, Left, Right};
+use iced::{
+    keyboard::key::Named,
+    widget::{button, column, container, row, scrollable, text},
+    Alignment::Center,
+    Element,
+    Length::Fill,
+    Task,
+};
+
+use crate::{
+    collection::Collection,
+    store::{self, EntryStats, Stats},
+    Action,
+};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Start(Arc<Collection>),
+    KeyPressed(Named),
+    BackButton,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatsWidget {
+    selected_collection: Option<Arc<Collection>>,
+    stats: Stats,
+    entries: Vec<EntryStats>,
+    // (entry, confused with, count), most frequent first
+    confusions: Vec<(String, String, usize)>,
+    // (scheduler name, entries due by the end of today)
+    due: Vec<(&'static str, usize)>,
+}
+
+impl StatsWidget {
+    pub fn new() -> Self {
+        Self {
+            selected_collection: None,
+            stats: Stats::default(),
+            entries: Vec::new(),
+            confusions: Vec::new(),
+            due: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Either<Task<Message>, Action> {
+        match message {
+            Message::Start(c) => {
+                self.stats = store::or_warn("load stats", |s| s.stats(c.key())).unwrap_or_default();
+                self.entries =
+                    store::or_warn("load stats", |s| s.entry_stats(c.key())).unwrap_or_default();
+                self.confusions = store::or_warn("load confusions", |s| s.confusions(c.key()))
+                    .unwrap_or_default();
+                let end_of_today = store::start_of_today() + 24 * 60 * 60;
+                self.due = [("SM-2", "sm2"), ("FSRS", "fsrs")]
+                    .into_iter()
+                    .map(|(name, scheduler)| {
+                        let due = store::or_warn("load due entries", |s| {
+                            s.due_entries(scheduler, c.key(), end_of_today)
+                        });
+                        (name, due.map_or(0, |v| v.len()))
+                    })
+                    .collect();
+                self.selected_collection = Some(c);
+            }
+            Message::KeyPressed(Named::Escape | Named::Enter) | Message::BackButton => {
+                return Right(Action::ChangeScreen("setup_screen".into()))
+            }
+            Message::KeyPressed(_) => {}
+        }
+        Left(Task::none())
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let Some(coll) = &self.selected_collection else {
+            return container(text("Smthing not right")).center(Fill).into();
+        };
+        let accuracy = if self.stats.reviews == 0 {
+            0.
+        } else {
+            self.stats.correct as f32 / self.stats.reviews as f32 * 100.
+        };
+        container(
+            column![
+                text!("{coll}").size(32),
+                row![
+                    text!("Answers: {}", self.stats.reviews),
+                    text!("Accuracy: {accuracy:.0}%"),
+                    text!(
+                        "Phrases seen: {} / {}",
+                        self.stats.entries_seen,
+                        coll.words().len()
+                    ),
+                    text!(
+                        "Avg. time: {:.1}s",
+                        self.stats.avg_response_ms as f32 / 1000.
+                    ),
+                ]
+                .spacing(20),
+                text!(
+                    "Due today: {}",
+                    self.due
+                        .iter()
+                        .map(|(name, n)| format!("{name} {n}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                scrollable(column(self.entries.iter().map(|e| {
+                    let last_seen = DateTime::<Utc>::from_timestamp(e.last_seen, 0)
+                        .map_or(String::new(), |v| v.format("%Y-%m-%d").to_string());
+                    text!(
+                        "| {:^30} | {:>3} / {:<3} | {:^12} |",
+                        e.entry,
+                        e.correct,
+                        e.reviews,
+                        last_seen
+                    )
+                    .size(16)
+                    .into()
+                })))
+                .height(300),
+                text("Confused with").size(20),
+                scrollable(column(self.confusions.iter().map(|(entry, other, n)| {
+                    text!("| {entry:^30} | {other:^30} | {n:>3} |")
+                        .size(16)
+                        .into()
+                })))
+                .height(150),
+                button("Back").on_press(Message::BackButton),
+            ]
+            .spacing(10)
+            .align_x(Center),
+        )
+        .center(Fill)
+        .into()
+    }
+}
+
+impl Default for StatsWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
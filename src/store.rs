@@ -0,0 +1,874 @@
+// Everything saved between runs (reviews, schedules, settings, notes) lives in one SQLite database
+// in the user data directory.
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use chrono::{Local, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    checker::{hint_penalty, Verdict},
+    scheduler::Grade,
+};
+
+// outcomes counted as a correct answer, self graded flashcards included
+const CORRECT_OUTCOMES: &str = "('correct', 'variant', 'good', 'easy')";
+
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("langl")
+}
+
+// writes to a temporary file next to `path` and renames it over,
+// so a crash never leaves a half written file behind
+pub fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let result = (|| {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    Ok(result?)
+}
+
+// midnight of the local day, as a unix timestamp
+pub fn start_of_today() -> i64 {
+    Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|v| v.and_local_timezone(Local).earliest())
+        .map_or(Utc::now().timestamp(), |v| v.timestamp())
+}
+
+// one entry per schema version, applied in order and tracked with `PRAGMA user_version`
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE reviews (
+        id INTEGER PRIMARY KEY,
+        collection TEXT NOT NULL,
+        entry TEXT NOT NULL,
+        mode TEXT NOT NULL,
+        input TEXT NOT NULL,
+        outcome TEXT NOT NULL,
+        score REAL NOT NULL,
+        response_ms INTEGER NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+    CREATE INDEX reviews_collection ON reviews (collection, entry, timestamp);
+    CREATE TABLE schedule (
+        scheduler TEXT NOT NULL,
+        collection TEXT NOT NULL,
+        entry TEXT NOT NULL,
+        state TEXT NOT NULL,
+        due INTEGER NOT NULL,
+        PRIMARY KEY (scheduler, collection, entry)
+    );
+    CREATE TABLE settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE notes (
+        collection TEXT NOT NULL,
+        entry TEXT NOT NULL,
+        note TEXT NOT NULL,
+        PRIMARY KEY (collection, entry)
+    );
+    CREATE TABLE confusions (
+        collection TEXT NOT NULL,
+        entry TEXT NOT NULL,
+        other TEXT NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+",
+    "
+    ALTER TABLE reviews ADD COLUMN session INTEGER NOT NULL DEFAULT 0;
+",
+    "
+    CREATE TABLE scores (
+        mode TEXT NOT NULL,
+        collection TEXT NOT NULL,
+        profile TEXT NOT NULL,
+        score INTEGER NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+",
+    "
+    ALTER TABLE reviews ADD COLUMN hints INTEGER NOT NULL DEFAULT 0;
+",
+    "
+    CREATE TABLE introductions (
+        collection TEXT NOT NULL,
+        entry TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        PRIMARY KEY (collection, entry)
+    );
+",
+];
+
+#[derive(Debug, Clone)]
+pub struct Review {
+    pub collection: String,
+    pub entry: String,
+    // learn, test, leitner, ...
+    pub mode: String,
+    pub input: String,
+    // Verdict::kind
+    pub outcome: String,
+    pub score: f32,
+    pub response_ms: i64,
+    pub timestamp: i64,
+    // start of the session in ms, 0 for reviews logged before sessions were recorded
+    pub session: i64,
+    // hints used before answering
+    pub hints: usize,
+}
+
+impl Review {
+    pub fn new(
+        collection: &str,
+        entry: &str,
+        mode: &str,
+        input: &str,
+        verdict: &Verdict,
+        shown_at_ms: i64,
+        session: i64,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            collection: collection.to_owned(),
+            entry: entry.to_owned(),
+            mode: mode.to_owned(),
+            input: input.to_owned(),
+            outcome: verdict.kind().to_owned(),
+            score: verdict.score(),
+            response_ms: now.timestamp_millis() - shown_at_ms,
+            timestamp: now.timestamp(),
+            session,
+            hints: 0,
+        }
+    }
+
+    // a flashcard graded by the user, `outcome` is the grade
+    pub fn graded(
+        collection: &str,
+        entry: &str,
+        mode: &str,
+        grade: Grade,
+        shown_at_ms: i64,
+        session: i64,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            collection: collection.to_owned(),
+            entry: entry.to_owned(),
+            mode: mode.to_owned(),
+            input: String::new(),
+            outcome: grade.outcome().to_owned(),
+            score: grade.score(),
+            response_ms: now.timestamp_millis() - shown_at_ms,
+            timestamp: now.timestamp(),
+            session,
+            hints: 0,
+        }
+    }
+
+    // every hint takes a part of the score away
+    pub fn with_hints(mut self, hints: usize) -> Self {
+        self.hints = hints;
+        self.score *= hint_penalty(hints);
+        self
+    }
+
+    pub fn is_correct(&self) -> bool {
+        matches!(
+            self.outcome.as_str(),
+            "correct" | "variant" | "good" | "easy"
+        )
+    }
+}
+
+// which part of the history "Review mistakes" looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MistakeWindow {
+    Sessions(u32),
+    Days(u32),
+}
+
+// targets for every day, over all collections and modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyGoals {
+    pub new_words: u16,
+    pub reviews: u16,
+    pub minutes: u16,
+}
+
+// what was done since local midnight
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DayProgress {
+    pub new_words: usize,
+    pub reviews: usize,
+    pub ms: i64,
+}
+
+impl DayProgress {
+    // new words are a limit more than a target, so they don't count here
+    pub fn reached(&self, goals: &DailyGoals) -> bool {
+        self.reviews >= goals.reviews.into() && self.ms >= i64::from(goals.minutes) * 60_000
+    }
+
+    pub fn describe(&self, goals: &DailyGoals) -> String {
+        format!(
+            "{}/{} new words, {}/{} reviews, {}/{} min",
+            self.new_words,
+            goals.new_words,
+            self.reviews,
+            goals.reviews,
+            self.ms / 60_000,
+            goals.minutes
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub reviews: usize,
+    pub correct: usize,
+    pub entries_seen: usize,
+    pub avg_response_ms: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct EntryStats {
+    pub entry: String,
+    pub reviews: usize,
+    pub correct: usize,
+    pub last_seen: i64,
+}
+
+#[derive(Debug)]
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        let store = Self {
+            conn: Connection::open(path)?,
+        };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    pub fn open_default() -> Result<Self> {
+        fs::create_dir_all(data_dir())?;
+        Self::open(&data_dir().join("langl.db"))
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        let store = Self {
+            conn: Connection::open_in_memory()?,
+        };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    fn migrate(&self) -> Result<()> {
+        let version: usize = self
+            .conn
+            .query_row("PRAGMA user_version", [], |r| r.get(0))?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            self.conn.execute_batch(&format!(
+                "BEGIN; {migration} PRAGMA user_version = {}; COMMIT;",
+                i + 1
+            ))?;
+        }
+        Ok(())
+    }
+
+    pub fn record_review(&self, r: &Review) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO reviews (collection, entry, mode, input, outcome, score, response_ms, timestamp, session, hints)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                r.collection,
+                r.entry,
+                r.mode,
+                r.input,
+                r.outcome,
+                r.score,
+                r.response_ms,
+                r.timestamp,
+                r.session,
+                r.hints
+            ],
+        )?;
+        Ok(())
+    }
+
+    // review plus anything else the verdict says about the answer
+    pub fn record_answer(&self, review: &Review, verdict: &Verdict) -> Result<()> {
+        self.record_review(review)?;
+        if let Verdict::Confused { other } = verdict {
+            self.record_confusion(&review.collection, &review.entry, other)?;
+        }
+        Ok(())
+    }
+
+    // oldest first, every collection when `collection` is None
+    pub fn reviews(&self, collection: Option<&str>) -> Result<Vec<Review>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT collection, entry, mode, input, outcome, score, response_ms, timestamp, session, hints
+             FROM reviews WHERE ?1 IS NULL OR collection = ?1 ORDER BY timestamp, id",
+        )?;
+        let rows = stmt.query_map([collection], |r| {
+            Ok(Review {
+                collection: r.get(0)?,
+                entry: r.get(1)?,
+                mode: r.get(2)?,
+                input: r.get(3)?,
+                outcome: r.get(4)?,
+                score: r.get(5)?,
+                response_ms: r.get(6)?,
+                timestamp: r.get(7)?,
+                session: r.get(8)?,
+                hints: r.get(9)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    // entries answered wrong at least once within `window`, most recent mistake first
+    pub fn mistakes(&self, collection: &str, window: MistakeWindow) -> Result<Vec<String>> {
+        let (filter, n) = match window {
+            MistakeWindow::Sessions(n) => (
+                "session IN (SELECT DISTINCT session FROM reviews WHERE collection = ?1
+                             ORDER BY session DESC LIMIT ?2)",
+                n as i64,
+            ),
+            MistakeWindow::Days(n) => {
+                ("timestamp >= ?2", Utc::now().timestamp() - n as i64 * 86400)
+            }
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT entry FROM reviews
+             WHERE collection = ?1 AND outcome NOT IN {CORRECT_OUTCOMES} AND {filter}
+             GROUP BY entry ORDER BY MAX(timestamp) DESC"
+        ))?;
+        let rows = stmt.query_map(params![collection, n], |r| r.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    // scheduler specific card state, saved all at once
+    pub fn save_cards<'a>(
+        &mut self,
+        scheduler: &str,
+        collection: &str,
+        cards: impl IntoIterator<Item = (&'a str, String, i64)>,
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO schedule (scheduler, collection, entry, state, due)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (entry, state, due) in cards {
+                stmt.execute(params![scheduler, collection, entry, state, due])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    // (entry, state, due)
+    pub fn cards(&self, scheduler: &str, collection: &str) -> Result<Vec<(String, String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT entry, state, due FROM schedule WHERE scheduler = ?1 AND collection = ?2",
+        )?;
+        let rows = stmt.query_map([scheduler, collection], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    // most overdue first
+    pub fn due_entries(&self, scheduler: &str, collection: &str, now: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT entry FROM schedule WHERE scheduler = ?1 AND collection = ?2 AND due <= ?3
+             ORDER BY due",
+        )?;
+        let rows = stmt.query_map(params![scheduler, collection, now], |r| r.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn stats(&self, collection: &str) -> Result<Stats> {
+        Ok(self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*), COALESCE(SUM(outcome IN {CORRECT_OUTCOMES}), 0),
+                        COUNT(DISTINCT entry), COALESCE(AVG(response_ms), 0)
+                 FROM reviews WHERE collection = ?1"
+            ),
+            [collection],
+            |r| {
+                Ok(Stats {
+                    reviews: r.get(0)?,
+                    correct: r.get(1)?,
+                    entries_seen: r.get(2)?,
+                    avg_response_ms: r.get::<_, f64>(3)? as i64,
+                })
+            },
+        )?)
+    }
+
+    // least accurate first
+    pub fn entry_stats(&self, collection: &str) -> Result<Vec<EntryStats>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT entry, COUNT(*) AS n, SUM(outcome IN {CORRECT_OUTCOMES}) AS c, MAX(timestamp)
+             FROM reviews WHERE collection = ?1 GROUP BY entry ORDER BY CAST(c AS REAL) / n, n DESC"
+        ))?;
+        let rows = stmt.query_map([collection], |r| {
+            Ok(EntryStats {
+                entry: r.get(0)?,
+                reviews: r.get(1)?,
+                correct: r.get(2)?,
+                last_seen: r.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn setting(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", [key], |r| {
+                r.get(0)
+            })
+            .optional()?)
+    }
+
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            [key, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn note(&self, collection: &str, entry: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT note FROM notes WHERE collection = ?1 AND entry = ?2",
+                [collection, entry],
+                |r| r.get(0),
+            )
+            .optional()?)
+    }
+
+    // an empty note removes it
+    pub fn set_note(&self, collection: &str, entry: &str, note: &str) -> Result<()> {
+        if note.trim().is_empty() {
+            self.conn.execute(
+                "DELETE FROM notes WHERE collection = ?1 AND entry = ?2",
+                [collection, entry],
+            )?;
+        } else {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO notes (collection, entry, note) VALUES (?1, ?2, ?3)",
+                [collection, entry, note],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn record_confusion(&self, collection: &str, entry: &str, other: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO confusions (collection, entry, other, timestamp) VALUES (?1, ?2, ?3, ?4)",
+            params![collection, entry, other, Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    // (entry, confused with, count), most frequent first
+    pub fn confusions(&self, collection: &str) -> Result<Vec<(String, String, usize)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT entry, other, COUNT(*) AS n FROM confusions WHERE collection = ?1
+             GROUP BY entry, other ORDER BY n DESC",
+        )?;
+        let rows = stmt.query_map([collection], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    // `entry` was shown to the learner before being asked for the first time
+    pub fn introduce(&self, collection: &str, entry: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO introductions (collection, entry, timestamp) VALUES (?1, ?2, ?3)",
+            params![collection, entry, Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    // entries introduced or answered at least once
+    pub fn seen_entries(&self, collection: &str) -> Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT entry FROM reviews WHERE collection = ?1
+             UNION SELECT entry FROM introductions WHERE collection = ?1",
+        )?;
+        let rows = stmt.query_map([collection], |r| r.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    // new words introduced since `since`, in every collection
+    pub fn introduced_since(&self, since: i64) -> Result<usize> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) FROM introductions WHERE timestamp >= ?1",
+            [since],
+            |r| r.get(0),
+        )?)
+    }
+
+    // every answer counts for at most a minute, so time spent away is left out
+    pub fn today(&self) -> Result<DayProgress> {
+        let since = start_of_today();
+        let (reviews, ms) = self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(MIN(response_ms, 60000)), 0)
+             FROM reviews WHERE timestamp >= ?1",
+            [since],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+        Ok(DayProgress {
+            new_words: self.introduced_since(since)?,
+            reviews,
+            ms,
+        })
+    }
+
+    pub fn record_score(
+        &self,
+        mode: &str,
+        collection: &str,
+        profile: &str,
+        score: usize,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO scores (mode, collection, profile, score, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![mode, collection, profile, score, Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    // (profile, best score, when), best profile first
+    pub fn leaderboard(
+        &self,
+        mode: &str,
+        collection: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, usize, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT profile, MAX(score) AS best, MIN(timestamp) FROM scores
+             WHERE mode = ?1 AND collection = ?2 AND (profile, score) IN
+                 (SELECT profile, MAX(score) FROM scores WHERE mode = ?1 AND collection = ?2
+                  GROUP BY profile)
+             GROUP BY profile ORDER BY best DESC, MIN(timestamp) LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(params![mode, collection, limit], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+// the app keeps one connection open, made on first use
+static DEFAULT_STORE: Mutex<Option<Store>> = Mutex::new(None);
+
+// runs `f` on the default store
+pub fn with_default<T>(f: impl FnOnce(&mut Store) -> Result<T>) -> Result<T> {
+    let mut guard = DEFAULT_STORE.lock().unwrap_or_else(|e| e.into_inner());
+    let store = match &mut *guard {
+        Some(store) => store,
+        None => guard.insert(Store::open_default()?),
+    };
+    f(store)
+}
+
+// prints the error of a save and keeps it in `status` for the screen to show
+pub fn report<T>(status: &mut Option<String>, what: &str, result: Result<T>) -> Option<T> {
+    match result {
+        Ok(v) => Some(v),
+        Err(e) => {
+            println!("could not {what}: {e}");
+            *status = Some(format!("Could not {what}: {e}"));
+            None
+        }
+    }
+}
+
+// `or_warn` for saves the user should know about
+pub fn or_report<T>(
+    status: &mut Option<String>,
+    what: &str,
+    f: impl FnOnce(&mut Store) -> Result<T>,
+) -> Option<T> {
+    report(status, what, with_default(f))
+}
+
+// runs `f` on the default store, printing instead of failing
+// so a storage problem never interrupts a session
+pub fn or_warn<T>(what: &str, f: impl FnOnce(&mut Store) -> Result<T>) -> Option<T> {
+    match with_default(f) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            println!("could not {what}: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(
+        collection: &str,
+        entry: &str,
+        outcome: &str,
+        timestamp: i64,
+        session: i64,
+    ) -> Review {
+        Review {
+            collection: collection.into(),
+            entry: entry.into(),
+            mode: "learn".into(),
+            input: String::new(),
+            outcome: outcome.into(),
+            score: if outcome == "correct" { 1. } else { 0. },
+            response_ms: 1000,
+            timestamp,
+            session,
+            hints: 0,
+        }
+    }
+
+    fn user_version(store: &Store) -> usize {
+        store
+            .conn
+            .query_row("PRAGMA user_version", [], |r| r.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn migrate_new_database() {
+        let store = Store::open_in_memory().unwrap();
+        assert_eq!(user_version(&store), MIGRATIONS.len());
+        // running it again changes nothing
+        store.migrate().unwrap();
+        assert_eq!(user_version(&store), MIGRATIONS.len());
+    }
+
+    #[test]
+    fn migrate_from_first_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!("{} PRAGMA user_version = 1;", MIGRATIONS[0]))
+            .unwrap();
+        conn.execute(
+            "INSERT INTO reviews (collection, entry, mode, input, outcome, score, response_ms, timestamp)
+             VALUES ('c', 'kot', 'learn', 'cat', 'correct', 1, 500, 10)",
+            [],
+        )
+        .unwrap();
+        let store = Store { conn };
+        store.migrate().unwrap();
+        assert_eq!(user_version(&store), MIGRATIONS.len());
+        let reviews = store.reviews(Some("c")).unwrap();
+        assert_eq!(reviews.len(), 1);
+        assert_eq!((reviews[0].session, reviews[0].hints), (0, 0));
+        store.record_score("survival", "c", "p", 3).unwrap();
+        store.introduce("c", "kot").unwrap();
+    }
+
+    #[test]
+    fn reopen_database_file() {
+        let path = std::env::temp_dir().join(format!("langl-test-{}.db", std::process::id()));
+        {
+            let store = Store::open(&path).unwrap();
+            store.set_setting("direction", "Mixed").unwrap();
+        }
+        let store = Store::open(&path).unwrap();
+        assert_eq!(
+            store.setting("direction").unwrap().as_deref(),
+            Some("Mixed")
+        );
+        drop(store);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reviews_oldest_first_by_collection() {
+        let store = Store::open_in_memory().unwrap();
+        store
+            .record_review(&review("a", "kot", "correct", 20, 1))
+            .unwrap();
+        store
+            .record_review(&review("a", "pies", "incorrect", 10, 1))
+            .unwrap();
+        store
+            .record_review(&review("b", "dom", "correct", 15, 1))
+            .unwrap();
+        let entries = |c| {
+            store
+                .reviews(c)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.entry)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(entries(Some("a")), ["pies", "kot"]);
+        assert_eq!(entries(None), ["pies", "dom", "kot"]);
+        let r = &store.reviews(Some("b")).unwrap()[0];
+        assert_eq!(
+            (r.outcome.as_str(), r.score, r.timestamp),
+            ("correct", 1., 15)
+        );
+    }
+
+    #[test]
+    fn mistakes_of_last_sessions() {
+        let store = Store::open_in_memory().unwrap();
+        store
+            .record_review(&review("c", "kot", "incorrect", 10, 1))
+            .unwrap();
+        store
+            .record_review(&review("c", "pies", "correct", 20, 2))
+            .unwrap();
+        store
+            .record_review(&review("c", "dom", "typo", 30, 3))
+            .unwrap();
+        store
+            .record_review(&review("c", "las", "again", 40, 3))
+            .unwrap();
+        store
+            .record_review(&review("other", "kot", "incorrect", 50, 4))
+            .unwrap();
+        let mistakes = |n| store.mistakes("c", MistakeWindow::Sessions(n)).unwrap();
+        assert_eq!(mistakes(1), ["las", "dom"]);
+        assert_eq!(mistakes(2), ["las", "dom"]);
+        assert_eq!(mistakes(3), ["las", "dom", "kot"]);
+    }
+
+    #[test]
+    fn mistakes_of_last_days() {
+        let store = Store::open_in_memory().unwrap();
+        let now = Utc::now().timestamp();
+        store
+            .record_review(&review("c", "kot", "incorrect", now - 3 * 86400, 1))
+            .unwrap();
+        store
+            .record_review(&review("c", "pies", "incorrect", now - 60, 2))
+            .unwrap();
+        store
+            .record_review(&review("c", "dom", "good", now - 30, 2))
+            .unwrap();
+        let mistakes = |n| store.mistakes("c", MistakeWindow::Days(n)).unwrap();
+        assert_eq!(mistakes(1), ["pies"]);
+        assert_eq!(mistakes(7), ["pies", "kot"]);
+    }
+
+    #[test]
+    fn due_entries_most_overdue_first() {
+        let mut store = Store::open_in_memory().unwrap();
+        let cards = [("kot", 10), ("pies", 5), ("dom", 100)]
+            .map(|(entry, due)| (entry, String::new(), due));
+        store.save_cards("sm2", "c", cards).unwrap();
+        store
+            .save_cards("leitner", "c", [("las", "0".into(), 0)])
+            .unwrap();
+        assert_eq!(store.due_entries("sm2", "c", 50).unwrap(), ["pies", "kot"]);
+        assert!(store.due_entries("sm2", "other", 50).unwrap().is_empty());
+        // saving again replaces the card
+        store
+            .save_cards("sm2", "c", [("pies", String::new(), 60)])
+            .unwrap();
+        assert_eq!(store.due_entries("sm2", "c", 50).unwrap(), ["kot"]);
+    }
+
+    #[test]
+    fn stats_and_entry_stats() {
+        let store = Store::open_in_memory().unwrap();
+        assert_eq!(store.stats("c").unwrap().reviews, 0);
+        store
+            .record_review(&review("c", "kot", "correct", 10, 1))
+            .unwrap();
+        store
+            .record_review(&review("c", "kot", "incorrect", 20, 1))
+            .unwrap();
+        store
+            .record_review(&review("c", "pies", "easy", 30, 1))
+            .unwrap();
+        store
+            .record_review(&review("c", "dom", "typo", 40, 1))
+            .unwrap();
+        let stats = store.stats("c").unwrap();
+        assert_eq!(
+            (
+                stats.reviews,
+                stats.correct,
+                stats.entries_seen,
+                stats.avg_response_ms
+            ),
+            (4, 2, 3, 1000)
+        );
+        let entries = store.entry_stats("c").unwrap();
+        let summary = entries
+            .iter()
+            .map(|e| (e.entry.as_str(), e.reviews, e.correct, e.last_seen))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [("dom", 1, 0, 40), ("kot", 2, 1, 20), ("pies", 1, 1, 30)]
+        );
+    }
+
+    #[test]
+    fn empty_note_is_deleted() {
+        let store = Store::open_in_memory().unwrap();
+        assert_eq!(store.note("c", "kot").unwrap(), None);
+        store.set_note("c", "kot", "cat, not a coat").unwrap();
+        assert_eq!(
+            store.note("c", "kot").unwrap().as_deref(),
+            Some("cat, not a coat")
+        );
+        store.set_note("c", "kot", "  ").unwrap();
+        assert_eq!(store.note("c", "kot").unwrap(), None);
+    }
+
+    #[test]
+    fn leaderboard_best_score_per_profile() {
+        let store = Store::open_in_memory().unwrap();
+        for (profile, score) in [("ala", 5), ("ola", 7), ("ala", 9), ("ela", 2), ("ola", 3)] {
+            store.record_score("survival", "c", profile, score).unwrap();
+        }
+        store.record_score("survival", "other", "ela", 50).unwrap();
+        store.record_score("sprint", "c", "ela", 50).unwrap();
+        let board = store.leaderboard("survival", "c", 10).unwrap();
+        let board = board
+            .iter()
+            .map(|(p, s, _)| (p.as_str(), *s))
+            .collect::<Vec<_>>();
+        assert_eq!(board, [("ala", 9), ("ola", 7), ("ela", 2)]);
+        assert_eq!(store.leaderboard("survival", "c", 2).unwrap().len(), 2);
+    }
+}
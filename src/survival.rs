@@ -0,0 +1,249 @@
+use std::sync::Arc;
+
+use crate::{
+    checker::{AnswerChecker, Verdict},
+    collection::Collection,
+    sprint::last_answer,
+    store::{self, Review},
+    Action,
+};
+use chrono::{DateTime, Utc};
+use either::Either::{self, Left, Right};
+use iced::{
+    keyboard::key::Named,
+    widget::{column, container, text, text_input},
+    Alignment::Center,
+    Color, Element,
+    Length::Fill,
+    Task,
+};
+
+const LIVES: usize = 3;
+const LEADERBOARD_SIZE: usize = 10;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    // collection, require all meanings, profile
+    Start(Arc<Collection>, bool, String),
+    InputChanged(String),
+    InputSubmit,
+    KeyPressed(Named),
+}
+
+#[derive(Debug, Clone)]
+pub struct SurvivalWidget {
+    selected_collection: Option<Arc<Collection>>,
+    checker: AnswerChecker,
+    profile: String,
+    word: String,
+    inputed: String,
+    // (word, verdict) of the last answer
+    last: Option<(String, Verdict)>,
+    lives: usize,
+    // correct answers so far
+    streak: usize,
+    // (profile, best streak, when)
+    leaderboard: Vec<(String, usize, i64)>,
+    start_message: bool,
+    end_message: bool,
+    // in ms
+    session: i64,
+    shown_at: i64,
+    // last error saving to the store, shown on the screen
+    save_status: Option<String>,
+}
+
+impl SurvivalWidget {
+    pub fn new() -> Self {
+        Self {
+            selected_collection: None,
+            checker: AnswerChecker::new(),
+            profile: String::new(),
+            word: String::new(),
+            inputed: String::new(),
+            last: None,
+            lives: LIVES,
+            streak: 0,
+            leaderboard: Vec::new(),
+            start_message: true,
+            end_message: false,
+            session: 0,
+            shown_at: 0,
+            save_status: None,
+        }
+    }
+
+    fn load_leaderboard(&mut self) {
+        let coll = self.selected_collection.as_ref().unwrap();
+        self.leaderboard = store::or_warn("load leaderboard", |s| {
+            s.leaderboard("survival", coll.key(), LEADERBOARD_SIZE)
+        })
+        .unwrap_or_default();
+    }
+
+    fn next_word(&mut self) {
+        let coll = self.selected_collection.as_ref().unwrap();
+        self.word = coll.random_word(&self.word);
+        self.shown_at = Utc::now().timestamp_millis();
+    }
+
+    pub fn update(&mut self, message: Message) -> Either<Task<Message>, Action> {
+        match message {
+            Message::Start(c, require_all, profile) => {
+                self.checker = AnswerChecker::for_session(&c, require_all);
+                self.profile = profile;
+                self.selected_collection = Some(c);
+                self.load_leaderboard();
+                self.inputed.clear();
+                self.last = None;
+                self.lives = LIVES;
+                self.streak = 0;
+                self.save_status = None;
+                self.start_message = true;
+                self.end_message = false;
+            }
+            Message::InputChanged(input) => self.inputed = input,
+            Message::InputSubmit => {
+                if self.end_message || self.inputed.is_empty() {
+                    return Left(Task::none());
+                }
+                let coll = self.selected_collection.clone().unwrap();
+                let verdict = self.checker.check_entry(&coll, &self.word, &self.inputed);
+                let review = Review::new(
+                    coll.key(),
+                    &self.word,
+                    "survival",
+                    &self.inputed,
+                    &verdict,
+                    self.shown_at,
+                    self.session,
+                );
+                store::or_report(&mut self.save_status, "save review", |s| {
+                    s.record_answer(&review, &verdict)
+                });
+                if verdict.is_correct() {
+                    self.streak += 1;
+                } else {
+                    self.lives -= 1;
+                }
+                self.last = Some((self.word.clone(), verdict));
+                self.inputed.clear();
+                if self.lives == 0 {
+                    self.end_message = true;
+                    store::or_report(&mut self.save_status, "save score", |s| {
+                        s.record_score("survival", coll.key(), &self.profile, self.streak)
+                    });
+                    self.load_leaderboard();
+                } else {
+                    self.next_word();
+                }
+            }
+            Message::KeyPressed(Named::Escape) => {
+                return Right(Action::ChangeScreen("setup_screen".into()))
+            }
+            Message::KeyPressed(Named::Enter) => {
+                if self.end_message {
+                    return Right(Action::ChangeScreen("setup_screen".into()));
+                }
+                if self.start_message {
+                    self.start_message = false;
+                    self.session = Utc::now().timestamp_millis();
+                    self.next_word();
+                    return Left(text_input::focus("survival_input_id"));
+                }
+            }
+            Message::KeyPressed(_) => {}
+        }
+        Left(Task::none())
+    }
+
+    fn leaderboard_view(&self) -> Element<'_, Message> {
+        column(
+            self.leaderboard
+                .iter()
+                .enumerate()
+                .map(|(i, (profile, score, ts))| {
+                    let date = DateTime::<Utc>::from_timestamp(*ts, 0)
+                        .map_or(String::new(), |v| v.format("%Y-%m-%d").to_string());
+                    let line = text!(
+                        "| {:>2} | {:^20} | {:>4} | {:^12} |",
+                        i + 1,
+                        profile,
+                        score,
+                        date
+                    )
+                    .size(16);
+                    if *profile == self.profile {
+                        line.color(Color::new(0., 0.4, 0.8, 1.))
+                    } else {
+                        line
+                    }
+                    .into()
+                }),
+        )
+        .spacing(2)
+        .into()
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let Some(coll) = &self.selected_collection else {
+            return container(text("Smthing not right")).center(Fill).into();
+        };
+        if coll.words().is_empty() {
+            return container(text("Collection is empty")).center(Fill).into();
+        }
+        if self.start_message || self.end_message {
+            let header = if self.start_message {
+                column![
+                    text("Survival").size(64),
+                    text("Press ENTER to start").size(12),
+                    text!("Playing as {}, {LIVES} mistakes end the run", self.profile).size(20),
+                ]
+            } else {
+                column![
+                    text("Game over").size(64),
+                    text("Press ENTER to exit").size(12),
+                    text!("Streak: {}", self.streak).size(24),
+                ]
+            };
+            return container(
+                header
+                    .push(text("Leaderboard").size(20))
+                    .push(self.leaderboard_view())
+                    .push_maybe(self.save_status.as_ref().map(|s| text(s).size(12)))
+                    .spacing(10)
+                    .align_x(Center),
+            )
+            .center(Fill)
+            .into();
+        }
+        let last = self
+            .last
+            .as_ref()
+            .map(|(word, verdict)| last_answer(coll, word, verdict));
+        container(
+            column![
+                text!("Lives: {} / {LIVES}", self.lives).size(20),
+                text!("Streak: {}", self.streak).size(16),
+                text(&self.word).size(48),
+                text_input("Type...", &self.inputed)
+                    .id("survival_input_id")
+                    .on_input(Message::InputChanged)
+                    .on_submit(Message::InputSubmit),
+            ]
+            .push_maybe(last)
+            .push_maybe(self.save_status.as_ref().map(|s| text(s).size(12)))
+            .spacing(20)
+            .align_x(Center)
+            .width(400),
+        )
+        .center(Fill)
+        .into()
+    }
+}
+
+impl Default for SurvivalWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,4 +1,8 @@
-use std::{fmt::format, fs::File, io::Write, path::PathBuf, sync::Arc};
+use std::{
+    fmt::{format, Write},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use chrono::Utc;
 use either::Either::{self, Left, Right};
@@ -13,15 +17,24 @@ use iced::{
 use rand::Rng;
 use rfd::FileDialog;
 
-use crate::{collection::Collection, Action};
+use crate::{
+    checker::{hint, hint_penalty, AnswerChecker, Verdict, MAX_HINTS},
+    collection::{Collection, Direction},
+    store::{self, Review},
+    Action,
+};
 
 #[derive(Debug, Clone)]
 pub enum Message {
     InputChanged(String),
     InputSubmit,
     SaveResults,
-    Start(Arc<Collection>, usize),
+    Start(Arc<Collection>, usize, bool, Direction),
+    // exactly these entries, in this order
+    StartWith(Arc<Collection>, Vec<String>, bool, Direction),
+    RetryWrong,
     KeyPressed(Named),
+    HintButton,
     SaveFile(Option<PathBuf>),
 }
 
@@ -36,7 +49,11 @@ enum Answer {
 pub struct TestWidget {
     selected_collection: Option<Arc<Collection>>,
     selected_collection_words: Option<Vec<String>>,
-    answers: Vec<(String, String, bool, i64)>,
+    checker: AnswerChecker,
+    // (word, input, verdict, timestamp, hints)
+    answers: Vec<(String, String, Verdict, i64, usize)>,
+    // hints asked for the current word
+    hints: usize,
     answer: Answer,
     word_index: usize,
     inputed: String,
@@ -44,6 +61,16 @@ pub struct TestWidget {
     start_message: bool,
     end_message: bool,
     start_time: i64,
+    // when the current word was shown, in ms
+    shown_at: i64,
+    // when the session started, in ms
+    session: i64,
+    require_all: bool,
+    direction: Direction,
+    // meaning shown instead of the word, for every word asked backwards
+    reverse: Vec<Option<String>>,
+    // result of the last "Save results", or the last error saving a review
+    save_status: Option<String>,
 }
 impl TestWidget {
     pub fn new() -> Self {
@@ -52,13 +79,21 @@ impl TestWidget {
             word_index: 0,
             answer: Answer::None,
             answers: Vec::new(),
+            hints: 0,
             inputed: String::new(),
             selected_collection_words: None,
+            checker: AnswerChecker::new(),
             words_number: 0,
             start_message: true,
             end_message: false,
 
             start_time: 0,
+            shown_at: 0,
+            session: 0,
+            require_all: false,
+            direction: Direction::Forward,
+            reverse: Vec::new(),
+            save_status: None,
         }
     }
     pub fn update(&mut self, message: Message) -> Either<Task<Message>, Action> {
@@ -73,16 +108,33 @@ impl TestWidget {
                     .get(self.word_index)
                     // .map(|v| v.as_str())
                     .unwrap();
-                let is_correct = self
-                    .selected_collection
-                    .clone()
-                    .unwrap()
-                    .words()
-                    .get(word)
-                    .unwrap()
-                    .contains(&self.inputed);
-                self.answers
-                    .push((word.clone(), self.inputed.clone(), is_correct, sub_time));
+                let coll = self.selected_collection.clone().unwrap();
+                let verdict = match &self.reverse[self.word_index] {
+                    Some(meaning) => self.checker.check_meaning(&coll, meaning, &self.inputed),
+                    None => self.checker.check_entry(&coll, word, &self.inputed),
+                };
+                let review = Review::new(
+                    coll.key(),
+                    word,
+                    "test",
+                    &self.inputed,
+                    &verdict,
+                    self.shown_at,
+                    self.session,
+                )
+                .with_hints(self.hints);
+                store::or_report(&mut self.save_status, "save review", |s| {
+                    s.record_answer(&review, &verdict)
+                });
+                self.shown_at = Utc::now().timestamp_millis();
+                self.answers.push((
+                    word.clone(),
+                    self.inputed.clone(),
+                    verdict,
+                    sub_time,
+                    self.hints,
+                ));
+                self.hints = 0;
 
                 if self.word_index < self.selected_collection_words.as_ref().unwrap().len() - 1 {
                     self.word_index += 1
@@ -91,21 +143,16 @@ impl TestWidget {
                 }
                 self.inputed.clear();
             }
-            Message::Start(c, n) => {
-                self.selected_collection = Some(c.clone());
-                self.words_number = n;
-                self.answer = Answer::None;
-                self.inputed.clear();
-                self.answers.clear();
-                self.word_index = 0;
+            Message::Start(c, n, require_all, direction) => {
+                let mut n = n;
                 if c.words().len() < n {
                     println!("there is not enough words in collection");
-                    self.words_number = c.words().len();
+                    n = c.words().len();
                 }
                 let mut words = Vec::new();
                 let keys = c.words().keys().collect::<Vec<_>>();
                 let mut used = Vec::new();
-                while words.len() < self.words_number {
+                while words.len() < n {
                     let rnd = rand::thread_rng().gen_range(0..c.words().len());
                     if used.contains(&rnd) {
                         continue;
@@ -113,8 +160,16 @@ impl TestWidget {
                     used.push(rnd);
                     words.push(keys[rnd].clone())
                 }
-                self.selected_collection_words = Some(words);
-                return Left(text_input::focus("testing_input_id"));
+                return Left(self.begin(c, words, require_all, direction));
+            }
+            Message::StartWith(c, words, require_all, direction) => {
+                return Left(self.begin(c, words, require_all, direction));
+            }
+            Message::RetryWrong => {
+                let words = self.wrong_words();
+                if let (Some(c), false) = (self.selected_collection.clone(), words.is_empty()) {
+                    return Left(self.begin(c, words, self.require_all, self.direction));
+                }
             }
             Message::KeyPressed(k) => match k {
                 Named::Escape => return Right(Action::ChangeScreen("setup_screen".into())),
@@ -126,10 +181,18 @@ impl TestWidget {
                     if self.start_message {
                         self.start_message = false;
                         self.start_time = Utc::now().timestamp();
+                        self.shown_at = Utc::now().timestamp_millis();
                     }
                 }
+                Named::Tab => return self.update(Message::HintButton),
                 _ => {}
             },
+            Message::HintButton => {
+                if !self.start_message && !self.end_message {
+                    self.hints = (self.hints + 1).min(MAX_HINTS);
+                    return Left(text_input::focus("testing_input_id"));
+                }
+            }
             Message::SaveResults => {
                 return Left(Task::perform(
                     async {
@@ -144,32 +207,96 @@ impl TestWidget {
             }
             Message::SaveFile(p) => {
                 if let Some(p) = p {
-                    let mut file = File::create(p).unwrap();
+                    let mut file = String::new();
                     writeln!(file, "Start time");
                     writeln!(file, "{}", self.start_time);
                     writeln!(file, "\n");
-                    let correct = self.answers.iter().filter(|v| v.2).count();
-                    writeln!(file, "Correct\tAll");
-                    writeln!(file, "{correct}\t{}", self.answers.len());
+                    let correct = self.answers.iter().filter(|v| v.2.is_correct()).count();
+                    writeln!(file, "Correct\tScore\tAll");
+                    writeln!(file, "{correct}\t{}\t{}", self.score(), self.answers.len());
                     writeln!(file, "\n");
-                    for (word, answer, correct, ts) in self.answers.iter() {
+                    for (word, answer, verdict, ts, hints) in self.answers.iter() {
                         writeln!(
                             file,
-                            "{}\t{}\t{}\t{}",
+                            "{}\t{}\t{}\t{}\t{}",
                             word,
                             if answer.is_empty() { "-" } else { answer },
-                            if *correct { 1 } else { 0 },
-                            ts
-                        )
-                        .unwrap();
+                            verdict.score() * hint_penalty(*hints),
+                            ts,
+                            answer_label(verdict, *hints)
+                        );
                     }
+                    self.save_status = Some(match store::write_atomic(&p, &file) {
+                        Ok(()) => format!("Saved to {}", p.display()),
+                        Err(e) => format!("Could not save results: {e}"),
+                    });
                 }
             }
         }
         Left(Task::none())
     }
 
-    pub fn view(&self) -> Element<Message> {
+    fn begin(
+        &mut self,
+        c: Arc<Collection>,
+        words: Vec<String>,
+        require_all: bool,
+        direction: Direction,
+    ) -> Task<Message> {
+        self.checker = AnswerChecker::for_session(&c, require_all);
+        self.reverse = words
+            .iter()
+            .map(|w| direction.reverse_prompt(&c, w))
+            .collect();
+        self.selected_collection = Some(c);
+        self.require_all = require_all;
+        self.direction = direction;
+        self.words_number = words.len();
+        self.answer = Answer::None;
+        self.inputed.clear();
+        self.answers.clear();
+        self.hints = 0;
+        self.save_status = None;
+        self.word_index = 0;
+        self.start_message = true;
+        self.end_message = false;
+        self.session = Utc::now().timestamp_millis();
+        self.selected_collection_words = Some(words);
+        text_input::focus("testing_input_id")
+    }
+
+    // each wrongly answered entry once, in the order they were asked
+    fn wrong_words(&self) -> Vec<String> {
+        let mut words: Vec<String> = Vec::new();
+        for (word, _, verdict, _, _) in &self.answers {
+            if !verdict.is_correct() && !words.contains(word) {
+                words.push(word.clone());
+            }
+        }
+        words
+    }
+
+    fn hint(&self) -> Option<String> {
+        if self.hints == 0 {
+            return None;
+        }
+        let coll = self.selected_collection.as_ref()?;
+        let word = &self.selected_collection_words.as_ref()?[self.word_index];
+        let answer = match &self.reverse[self.word_index] {
+            Some(meaning) => coll.words_for(meaning).first(),
+            None => coll.words().get(word)?.first(),
+        }?;
+        Some(hint(answer, self.hints))
+    }
+
+    fn score(&self) -> f32 {
+        self.answers
+            .iter()
+            .map(|v| v.2.score() * hint_penalty(v.4))
+            .sum()
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
         if self.selected_collection.is_none() {
             return container(text("Smthing not right")).center(Fill).into();
         }
@@ -185,19 +312,25 @@ impl TestWidget {
                 column![
                     text("Test finished").size(64),
                     text("Press ENTER to exit").size(12),
+                    text!("Score: {} / {}", self.score(), self.answers.len()).size(24),
                     scrollable(column(self.answers.iter().map(|v| {
                         text!(
                             "| {:^30} | {:^30} | {:^12} |",
                             v.0,
                             if v.1.is_empty() { "-" } else { &v.1 },
-                            if v.2 { "Correct" } else { "Incorrect" }
+                            answer_label(&v.2, v.4)
                         )
                         .size(16)
                         .into()
                     })))
                     .height(100),
-                    button("Save results").on_press(Message::SaveResults)
+                    row![button("Save results").on_press(Message::SaveResults)]
+                        .push_maybe((!self.wrong_words().is_empty()).then(|| {
+                            button("Retry wrong answers").on_press(Message::RetryWrong)
+                        }))
+                        .spacing(10)
                 ]
+                .push_maybe(self.save_status.as_ref().map(|s| text(s).size(12)))
                 .spacing(10)
                 .align_x(Center),
             )
@@ -214,12 +347,18 @@ impl TestWidget {
         } else {
             container(
                 column![
-                    text(word).size(48),
+                    text(self.reverse[self.word_index].as_deref().unwrap_or(word)).size(48),
                     text_input("Type...", &self.inputed)
                         .id("testing_input_id")
                         .on_input(Message::InputChanged)
                         .on_submit(Message::InputSubmit),
+                    row![button("Hint (Tab)")
+                        .on_press_maybe((self.hints < MAX_HINTS).then_some(Message::HintButton))]
+                    .push_maybe(self.hint().map(|h| text(h).size(20)))
+                    .spacing(10)
+                    .align_y(Center),
                 ]
+                .push_maybe(self.save_status.as_ref().map(|s| text(s).size(12)))
                 .spacing(30)
                 .align_x(Center)
                 .width(400),
@@ -230,6 +369,14 @@ impl TestWidget {
     }
 }
 
+fn answer_label(verdict: &Verdict, hints: usize) -> String {
+    match hints {
+        0 => verdict.label(),
+        1 => format!("{}, 1 hint", verdict.label()),
+        n => format!("{}, {n} hints", verdict.label()),
+    }
+}
+
 impl Default for TestWidget {
     fn default() -> Self {
         Self::new()